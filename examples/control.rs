@@ -132,6 +132,7 @@ struct App<'a> {
     progress_tracker: ProgressTracker<'a>,
     stdin: termion::AsyncReader,
     screen: Screen,
+    supports_position: bool,
 }
 
 impl<'a> App<'a> {
@@ -184,6 +185,7 @@ impl<'a> App<'a> {
 
     fn tick_progress_and_refresh(&mut self, should_refresh: bool) {
         let supports_position = self.supports_position();
+        let position_advancing = self.progress_tracker.is_position_advancing();
         let ProgressTick {
             progress,
             progress_changed,
@@ -195,8 +197,8 @@ impl<'a> App<'a> {
         // Dirty tracking to keep CPU usage lower. In case nothing happened since the last refresh,
         // only update the progress bar.
         //
-        // If player doesn't support position handling, don't even try to refresh the progress bar
-        // if no event took place.
+        // If player doesn't support position handling, or the position is not moving, don't even
+        // try to refresh the progress bar if no event took place.
         if progress_changed || track_list_changed || should_refresh {
             let current_track_id = progress.metadata().track_id();
             clear_screen(&mut self.screen);
@@ -207,7 +209,7 @@ impl<'a> App<'a> {
                 print_track_list(&mut self.screen, tracks, next_track);
             }
             print_progress_bar(&mut self.screen, progress, supports_position);
-        } else if supports_position {
+        } else if supports_position && position_advancing {
             clear_progress_bar(&mut self.screen);
             print_progress_bar(&mut self.screen, progress, supports_position);
         }
@@ -216,7 +218,7 @@ impl<'a> App<'a> {
     }
 
     fn supports_position(&self) -> bool {
-        self.player.identity() != "Spotify"
+        self.supports_position
     }
 }
 
@@ -272,7 +274,7 @@ fn control_player(result: Result<(), mpris::DBusError>) {
 }
 
 fn toggle_shuffle(player: &Player) -> Result<(), mpris::DBusError> {
-    player.set_shuffle(!player.get_shuffle()?)
+    player.toggle_shuffle().map(|_| ())
 }
 
 fn cycle_loop_status(player: &Player) -> Result<(), mpris::DBusError> {
@@ -374,19 +376,11 @@ fn find_next_track(
     track_list: &TrackList,
     player: &Player,
 ) -> Option<Metadata> {
-    if let Some(current_id) = current_track_id {
-        track_list
-            .metadata_iter(player)
-            .ok()?
-            .skip_while(|track| match track.track_id() {
-                // Stops on current track
-                Some(id) => id != current_id,
-                None => false,
-            })
-            .nth(1) // Skip one more to get the next one
-    } else {
-        None
-    }
+    let current_index = track_list.position_of(&current_track_id?)?;
+    track_list
+        .metadata_iter(player)
+        .ok()?
+        .nth(current_index + 1)
 }
 
 fn print_progress_bar(screen: &mut Screen, progress: &Progress, supports_position: bool) {
@@ -442,6 +436,15 @@ fn format_duration(duration: Duration) -> String {
     format!("{:02}:{:02}:{:02}", whole_hours, whole_minutes, secs)
 }
 
+fn supports_position(player: &Player) -> bool {
+    // The position only moves while playing, so assume that it works if the player is paused or
+    // stopped.
+    let playing = player
+        .get_playback_status()
+        .map_or(false, PlaybackStatus::is_playing);
+    !playing || player.probe_position_support().unwrap_or(true)
+}
+
 fn main() {
     let player = PlayerFinder::new()
         .unwrap()
@@ -462,6 +465,7 @@ fn main() {
         progress_tracker,
         screen,
         stdin: termion::async_stdin(),
+        supports_position: supports_position(&player),
     };
 
     app.main_loop();
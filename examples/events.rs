@@ -1,4 +1,4 @@
-use mpris::PlayerFinder;
+use mpris::{Event, Metadata, PlayerFinder};
 use std::time::{Duration, Instant};
 
 fn main() {
@@ -17,6 +17,13 @@ fn main() {
 
     for event in events {
         match event {
+            Ok(Event::TrackChanged { old, new }) => println!(
+                "{}: Track changed from {} to {}",
+                format_elapsed(start.elapsed()),
+                old.as_ref()
+                    .map_or_else(|| String::from("nothing"), format_track),
+                format_track(&new),
+            ),
             Ok(event) => println!("{}: {:#?}", format_elapsed(start.elapsed()), event),
             Err(err) => {
                 println!("D-Bus error: {}. Aborting.", err);
@@ -35,3 +42,11 @@ fn format_elapsed(duration: Duration) -> String {
     let ms = duration.subsec_millis();
     format!("{:02}:{:02}.{:3}", minutes, seconds_left, ms)
 }
+
+fn format_track(metadata: &Metadata) -> String {
+    let title = metadata.title().unwrap_or("Unknown title");
+    match metadata.artists() {
+        Some(artists) if !artists.is_empty() => format!("{} - {}", artists.join(", "), title),
+        _ => String::from(title),
+    }
+}
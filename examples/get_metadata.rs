@@ -32,7 +32,19 @@ fn print_metadata() -> Result<()> {
         .get_metadata()
         .context("Could not get metadata for player")?;
 
-    println!("Metadata:\n{:#?}\n", metadata);
+    let mut keys: Vec<&str> = metadata.keys().collect();
+    keys.sort_unstable();
+
+    println!("Metadata:");
+    for key in keys {
+        if let Some(value) = metadata.get(key) {
+            println!(
+                "  {}: {}",
+                key,
+                value.to_pretty_string(2).replace('\n', "\n  ")
+            );
+        }
+    }
 
     Ok(())
 }
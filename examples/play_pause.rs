@@ -22,19 +22,16 @@ fn play_pause() -> Result<PlaybackStatus, String> {
         .find_active()
         .map_err(|e| format!("Could not find any player: {}", e))?;
 
-    let toggled = player
-        .checked_play_pause()
+    let can_pause = player
+        .can_pause()
         .map_err(|e| format!("Could not control player: {}", e))?;
 
-    if toggled {
-        // Give the player some time to respond to the message and update its properties. The
-        // play_pause() call will wait for a reply, but the player might not update the properties
-        // before replying.
-        std::thread::sleep(std::time::Duration::from_millis(50));
-
+    if can_pause {
+        // The player might not update its properties before replying to the message, so wait for
+        // the status to change.
         player
-            .get_playback_status()
-            .map_err(|e| format!("Could not get playback status: {}", e))
+            .play_pause_and_status(std::time::Duration::from_millis(500))
+            .map_err(|e| format!("Could not control player: {}", e))
     } else {
         // Could not toggle play/pause status. This happens when the media cannot be paused, which
         // could be because of any number of reasons including:
@@ -1,7 +1,7 @@
 use std::io::{stdout, Write};
 use std::time::Duration;
 
-use mpris::{LoopStatus, Metadata, PlaybackStatus, PlayerFinder, Progress, ProgressTick};
+use mpris::{LoopStatus, Metadata, PlaybackStatus, Player, PlayerFinder, Progress, ProgressTick};
 
 fn reset_line() {
     print!("\r\x1b[K");
@@ -65,13 +65,23 @@ fn print_loop_status(progress: &Progress) {
     }
 }
 
+fn supports_position(player: &Player) -> bool {
+    // The position only moves while playing, so assume that it works if the player is paused or
+    // stopped.
+    let playing = player
+        .get_playback_status()
+        .map_or(false, PlaybackStatus::is_playing);
+    !playing || player.probe_position_support().unwrap_or(true)
+}
+
 fn main() {
     let player = PlayerFinder::new().unwrap().find_active().unwrap();
     let identity = player.identity();
+    let supports_position = supports_position(&player);
 
     let mut progress_tracker = player.track_progress(100).unwrap();
     loop {
-        let ProgressTick { progress, .. } = progress_tracker.tick();
+        let ProgressTick { progress, .. } = progress_tracker.tick_until_change();
 
         reset_line();
         print_playback_status(progress);
@@ -82,7 +92,7 @@ fn main() {
         print!(" - ");
         print_title(progress.metadata());
         print!(" [");
-        if identity != "Spotify" {
+        if supports_position {
             print_time(Some(progress.position()));
         } else {
             print_time(None);
@@ -63,17 +63,17 @@ fn print_track_list(player: &Player) -> Result<()> {
 
     println!("Track list:\n");
     let iter = track_list
-        .metadata_iter(player)
+        .iter_with_ids(player)
         .context("Could not load metadata for tracks")?;
 
-    for (index, metadata) in iter.enumerate() {
+    for (index, (id, metadata)) in iter.enumerate() {
         let title = metadata.title().unwrap_or("Unknown title");
         let artist = metadata
             .artists()
             .map(|list| list.join(", "))
             .unwrap_or_else(|| "Unknown artist".into());
 
-        println!("{}. {} - {}", index + 1, artist, title);
+        println!("{}. {} - {} ({})", index + 1, artist, title, id);
     }
 
     Ok(())
@@ -2,14 +2,18 @@ use super::{
     DBusError, LoopStatus, Metadata, PlaybackStatus, Player, Progress, TrackID, TrackList,
     TrackListError,
 };
+use crate::extensions::DurationExtensions;
 use crate::pooled_connection::MprisEvent;
+use std::mem::discriminant;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 /// Represents a change in [`Player`] state.
 ///
-/// Note that this does not include position changes (seeking in a track or normal progress of time
-/// for playing media).
-#[derive(Debug)]
+/// Note that normal progress of time for playing media is only reported when position polling is
+/// enabled with [`PlayerEvents::with_position_polling`].
+#[derive(Debug, Clone, PartialEq)]
 pub enum Event {
     /// [`Player`] was shut down / quit.
     PlayerShutDown,
@@ -32,11 +36,31 @@ pub enum Event {
     /// [`Player`]'s volume was changed. The new volume is provided.
     VolumeChanged(f64),
 
-    /// [`Player`]'s playback rate was changed. New playback rate is provided.
-    PlaybackRateChanged(f64),
+    /// [`Player`]'s playback rate, or the range of allowed playback rates, was changed.
+    PlaybackRateChanged {
+        /// The new playback rate.
+        rate: f64,
+
+        /// The allowed playback rates, from the player's `MinimumRate` to its `MaximumRate`, both
+        /// inclusive.
+        ///
+        /// This is [`None`] if the player does not report `MinimumRate` and `MaximumRate`. A rate
+        /// equal to `valid_range.end()` means that the rate is pinned to the maximum.
+        valid_range: Option<RangeInclusive<f64>>,
+    },
 
-    /// [`Player`]'s track changed. [`Metadata`] of the new track is provided.
-    TrackChanged(Metadata),
+    /// [`Player`]'s track changed. [`Metadata`] of both the previous and the new track is
+    /// provided.
+    TrackChanged {
+        /// [`Metadata`] of the track that was playing before the change.
+        ///
+        /// This is [`None`] if no track was loaded before the change, for example when the
+        /// player starts playing for the first time.
+        old: Option<Metadata>,
+
+        /// [`Metadata`] of the new track.
+        new: Metadata,
+    },
 
     /// [`Player`] seeked (changed position in the current track).
     ///
@@ -77,6 +101,12 @@ pub enum Event {
 
     /// The track list was replaced.
     TrackListReplaced,
+
+    /// [`Player`]'s position advanced while playing. The extrapolated position is provided.
+    ///
+    /// This is not a signal sent by the player; it is only emitted periodically when
+    /// [`PlayerEvents::with_position_polling`] is used.
+    PositionChanged(Duration),
 }
 
 /// Errors that can occur while processing event streams.
@@ -98,6 +128,8 @@ pub enum EventError {
 ///
 /// If multiple events are found between processing D-Bus events then all of them will be iterated
 /// in rapid succession before processing more events.
+///
+/// Use [`PlayerEvents::next_timeout`] if you need to wait for a bounded amount of time instead.
 #[derive(Debug)]
 pub struct PlayerEvents<'a> {
     /// [`Player`] to watch.
@@ -111,6 +143,25 @@ pub struct PlayerEvents<'a> {
 
     /// Current tracklist of the player. Will be kept up to date.
     track_list: Option<TrackList>,
+
+    /// Identical events emitted within this window of each other are suppressed.
+    dedup_window: Option<Duration>,
+
+    /// The last emitted event, if deduplication is enabled.
+    last_event: Option<(Instant, Event)>,
+
+    /// Interval between [`Event::PositionChanged`] events while playing, if enabled.
+    position_interval: Option<Duration>,
+
+    /// When the last [`Event::PositionChanged`] event was queued.
+    last_position_event: Instant,
+
+    /// Collapse consecutive volume, playback rate and seek events into the latest one.
+    coalescing: bool,
+
+    /// Cached range of allowed playback rates. Only refreshed when the player signals that the
+    /// bounds changed.
+    rate_range: Option<RangeInclusive<f64>>,
 }
 
 impl PlayerEvents<'_> {
@@ -121,18 +172,132 @@ impl PlayerEvents<'_> {
             buffer: Vec::new(),
             last_progress: progress,
             track_list: player.checked_get_track_list()?,
+            dedup_window: None,
+            last_event: None,
+            position_interval: None,
+            last_position_event: Instant::now(),
+            coalescing: true,
+            rate_range: player.checked_get_playback_rate_bounds()?,
         })
     }
 
+    /// Suppress events that are identical to the previously emitted event, if they happen within
+    /// `window` of it.
+    ///
+    /// Some players emit several property changes in quick succession for a single logical
+    /// change, which can lead to duplicate events.
+    pub fn deduped(mut self, window: Duration) -> Self {
+        self.dedup_window = Some(window);
+        self
+    }
+
+    /// Emit [`Event::PositionChanged`] every `interval` while the player is playing, in addition
+    /// to the events caused by signals from the player.
+    ///
+    /// The position is extrapolated from the last known state, so this does not cause any extra
+    /// D-Bus calls. This is useful for players that do not emit `Seeked` signals, or to drive a
+    /// progress display from the event stream alone.
+    pub fn with_position_polling(mut self, interval: Duration) -> Self {
+        self.position_interval = Some(interval);
+        self.last_position_event = Instant::now();
+        self
+    }
+
+    /// Enable or disable coalescing of events. Enabled by default.
+    ///
+    /// When a player sends a burst of changes, only the latest of several consecutive
+    /// [`Event::VolumeChanged`], [`Event::PlaybackRateChanged`] or [`Event::Seeked`] events is
+    /// emitted. Other events, like [`Event::TrackChanged`], are never collapsed.
+    pub fn set_coalescing(&mut self, coalescing: bool) {
+        self.coalescing = coalescing;
+    }
+
     /// Current tracklist of the player. Will be kept up to date.
     pub fn track_list(&self) -> Option<&TrackList> {
         self.track_list.as_ref()
     }
 
-    fn read_events(&mut self) -> Result<(), EventError> {
-        self.player.process_events_blocking_until_received();
+    /// Mutable access to the current tracklist of the player, for example to enable
+    /// [`TrackList::set_operations_limit`].
+    pub fn track_list_mut(&mut self) -> Option<&mut TrackList> {
+        self.track_list.as_mut()
+    }
+
+    fn is_duplicate(&self, event: &Event) -> bool {
+        match (self.dedup_window, &self.last_event) {
+            (Some(window), Some((emitted_at, last_event))) => {
+                emitted_at.elapsed() <= window && last_event == event
+            }
+            _ => false,
+        }
+    }
+
+    /// Waits for the next event, blocking for at most `timeout`, and returns it.
+    ///
+    /// Returns [`None`] if no event was found before the timeout ran out. Unlike the end of the
+    /// iterator, this does not mean that the stream has ended; you can keep calling this method
+    /// or [`next`](Iterator::next) afterwards. This also returns [`None`] right away if the player
+    /// is no longer running, so check [`Player::is_running`] if you need to tell them apart.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<Result<Event, EventError>> {
+        let start = Instant::now();
+
+        loop {
+            if let Some(event) = self.take_buffered_event() {
+                return Some(Ok(event));
+            }
+
+            if !self.player.is_running() {
+                return None;
+            }
+
+            let time_left = timeout.checked_sub(start.elapsed())?;
+            if let Err(err) = self.read_events(Some(time_left)) {
+                return Some(Err(err));
+            }
+
+            if self.buffer.is_empty() && start.elapsed() >= timeout {
+                return None;
+            }
+        }
+    }
+
+    /// Removes the next event from the buffer, skipping any duplicates.
+    fn take_buffered_event(&mut self) -> Option<Event> {
+        while !self.buffer.is_empty() {
+            let event = self.buffer.remove(0);
+            if self.is_duplicate(&event) {
+                continue;
+            }
+
+            if self.dedup_window.is_some() {
+                self.last_event = Some((Instant::now(), event.clone()));
+            }
+            return Some(event);
+        }
+        None
+    }
+
+    /// Reads events from the player, blocking for at most `timeout` if one is given.
+    fn read_events(&mut self, timeout: Option<Duration>) -> Result<(), EventError> {
+        let polling_timeout = self
+            .position_interval
+            .map(|interval| interval.saturating_sub(self.last_position_event.elapsed()));
+        let wait = match (timeout, polling_timeout) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        match wait {
+            Some(duration) if duration > Duration::from_millis(0) => {
+                self.player
+                    .process_events_blocking_until_received_for(duration);
+            }
+            Some(_) => {}
+            None => self.player.process_events_blocking_until_received(),
+        }
 
         let mut new_progress: Option<Progress> = None;
+        let mut reload_rate_range = false;
         let mut reload_track_list = false;
 
         for event in self.player.pending_events().into_iter() {
@@ -141,12 +306,21 @@ impl PlayerEvents<'_> {
                     self.buffer.push(Event::PlayerShutDown);
                     return Ok(());
                 }
-                MprisEvent::PlayerPropertiesChanged => {
+                MprisEvent::PlayerPropertiesChanged { changed } => {
+                    reload_rate_range |= changed
+                        .iter()
+                        .any(|name| name == "MinimumRate" || name == "MaximumRate");
                     if new_progress.is_none() {
                         new_progress = Some(Progress::from_player(self.player)?);
                     }
                 }
                 MprisEvent::Seeked { position_in_us } => {
+                    // Keep the extrapolated position in sync for `Event::PositionChanged`.
+                    let position = Duration::from_micros_ext(position_in_us);
+                    self.last_progress.seeked_to(position);
+                    if let Some(ref mut progress) = new_progress {
+                        progress.seeked_to(position);
+                    }
                     self.buffer.push(Event::Seeked { position_in_us })
                 }
                 MprisEvent::TrackListPropertiesChanged => {
@@ -188,11 +362,26 @@ impl PlayerEvents<'_> {
             self.detect_loop_status_events(&progress);
             reload_track_list |= self.detect_shuffle_events(&progress);
             self.detect_volume_events(&progress);
-            self.detect_playback_rate_events(&progress);
+            let rate_range = if reload_rate_range {
+                self.player.checked_get_playback_rate_bounds()?
+            } else {
+                self.rate_range.clone()
+            };
+            self.detect_playback_rate_events(&progress, rate_range);
             self.detect_metadata_events(&progress);
             self.last_progress = progress;
         }
 
+        if let Some(interval) = self.position_interval {
+            if self.last_position_event.elapsed() >= interval {
+                self.last_position_event = Instant::now();
+                if self.last_progress.playback_status().is_playing() {
+                    self.buffer
+                        .push(Event::PositionChanged(self.last_progress.position()));
+                }
+            }
+        }
+
         if reload_track_list && self.track_list.is_some() {
             if let Some(new_tracks) = self.player.checked_get_track_list()? {
                 match self.track_list {
@@ -203,6 +392,10 @@ impl PlayerEvents<'_> {
             }
         }
 
+        if self.coalescing {
+            coalesce_events(&mut self.buffer);
+        }
+
         Ok(())
     }
 
@@ -239,11 +432,21 @@ impl PlayerEvents<'_> {
         }
     }
 
-    fn detect_playback_rate_events(&mut self, new_progress: &Progress) {
+    fn detect_playback_rate_events(
+        &mut self,
+        new_progress: &Progress,
+        rate_range: Option<RangeInclusive<f64>>,
+    ) {
         let rate = new_progress.playback_rate();
-        if is_different_float(self.last_progress.playback_rate(), rate) {
-            self.buffer.push(Event::PlaybackRateChanged(rate));
+        if is_different_float(self.last_progress.playback_rate(), rate)
+            || is_different_range(self.rate_range.as_ref(), rate_range.as_ref())
+        {
+            self.buffer.push(Event::PlaybackRateChanged {
+                rate,
+                valid_range: rate_range.clone(),
+            });
         }
+        self.rate_range = rate_range;
     }
 
     fn detect_metadata_events(&mut self, new_progress: &Progress) {
@@ -259,7 +462,16 @@ impl PlayerEvents<'_> {
             || old_metadata.title() != new_metadata.title()
             || old_metadata.artists() != new_metadata.artists()
         {
-            self.buffer.push(Event::TrackChanged(new_metadata.clone()));
+            let old = if old_metadata.is_empty() {
+                None
+            } else {
+                Some(old_metadata.clone())
+            };
+
+            self.buffer.push(Event::TrackChanged {
+                old,
+                new: new_metadata.clone(),
+            });
         }
     }
 }
@@ -268,23 +480,148 @@ fn is_different_float(a: f64, b: f64) -> bool {
     (a - b).abs() >= ::std::f64::EPSILON
 }
 
+/// Collapses consecutive events of the same kind into the last one, for events where only the
+/// latest value is interesting.
+fn coalesce_events(events: &mut Vec<Event>) {
+    let mut coalesced: Vec<Event> = Vec::with_capacity(events.len());
+
+    for event in events.drain(..) {
+        match coalesced.last_mut() {
+            Some(last) if is_coalescable(last) && discriminant(last) == discriminant(&event) => {
+                *last = event;
+            }
+            _ => coalesced.push(event),
+        }
+    }
+
+    *events = coalesced;
+}
+
+fn is_coalescable(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::VolumeChanged(_) | Event::PlaybackRateChanged { .. } | Event::Seeked { .. }
+    )
+}
+
+fn is_different_range(a: Option<&RangeInclusive<f64>>, b: Option<&RangeInclusive<f64>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            is_different_float(*a.start(), *b.start()) || is_different_float(*a.end(), *b.end())
+        }
+        (None, None) => false,
+        _ => true,
+    }
+}
+
 impl<'a> Iterator for PlayerEvents<'a> {
     type Item = Result<Event, EventError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.buffer.is_empty() {
+        loop {
+            if let Some(event) = self.take_buffered_event() {
+                return Some(Ok(event));
+            }
+
             // Stop iteration when player is not running. Why beat a dead horse?
             if !self.player.is_running() {
                 return None;
             }
 
-            match self.read_events() {
+            match self.read_events(None) {
                 Ok(_) => {}
                 Err(err) => return Some(Err(err)),
             };
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_player::{TestPlayer, TestPlayerState};
+
+    #[test]
+    fn it_coalesces_consecutive_value_events() {
+        let mut events = vec![
+            Event::VolumeChanged(0.2),
+            Event::VolumeChanged(0.5),
+            Event::Seeked { position_in_us: 10 },
+            Event::Seeked { position_in_us: 20 },
+            Event::Paused,
+            Event::PlaybackRateChanged {
+                rate: 1.0,
+                valid_range: None,
+            },
+            Event::PlaybackRateChanged {
+                rate: 1.5,
+                valid_range: None,
+            },
+            Event::VolumeChanged(0.7),
+        ];
+
+        coalesce_events(&mut events);
+
+        assert_eq!(
+            events,
+            vec![
+                Event::VolumeChanged(0.5),
+                Event::Seeked { position_in_us: 20 },
+                Event::Paused,
+                Event::PlaybackRateChanged {
+                    rate: 1.5,
+                    valid_range: None,
+                },
+                Event::VolumeChanged(0.7),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_never_coalesces_track_changes() {
+        let first = Metadata::new("/first");
+        let second = Metadata::new("/second");
+        let mut events = vec![
+            Event::TrackChanged {
+                old: None,
+                new: first.clone(),
+            },
+            Event::TrackChanged {
+                old: Some(first),
+                new: second,
+            },
+            Event::Stopped,
+            Event::Stopped,
+        ];
+        let expected = events.clone();
+
+        coalesce_events(&mut events);
+
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn it_reports_position_after_a_seek() {
+        let test_player = TestPlayer::new(TestPlayerState::default());
+        let player = test_player.player();
+        let mut events = player
+            .events()
+            .unwrap()
+            .with_position_polling(Duration::from_millis(50));
+
+        test_player.emit_seeked(60_000_000);
+
+        let mut seeked = false;
+        let position = loop {
+            match events.next_timeout(Duration::from_secs(2)) {
+                Some(Ok(Event::Seeked { .. })) => seeked = true,
+                Some(Ok(Event::PositionChanged(position))) if seeked => break position,
+                Some(Ok(_)) => {}
+                other => panic!("Unexpected result: {:?}", other),
+            }
+        };
 
-        let event = self.buffer.remove(0);
-        Some(Ok(event))
+        assert!(position >= Duration::from_secs(60), "{:?}", position);
+        assert!(position < Duration::from_secs(61), "{:?}", position);
     }
 }
@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use std::time::Duration;
 
 pub(crate) trait DurationExtensions {
@@ -5,6 +6,7 @@ pub(crate) trait DurationExtensions {
     fn from_micros_ext(_: u64) -> Duration;
     fn as_millis(&self) -> u64;
     fn as_micros(&self) -> u64;
+    fn as_micros_i64(&self) -> i64;
 }
 
 impl DurationExtensions for Duration {
@@ -21,6 +23,15 @@ impl DurationExtensions for Duration {
     fn as_micros(&self) -> u64 {
         self.as_secs() * 1000 * 1000 + u64::from(self.subsec_micros())
     }
+
+    // Saturates at i64::MAX instead of overflowing for absurdly long durations.
+    fn as_micros_i64(&self) -> i64 {
+        let micros = self
+            .as_secs()
+            .saturating_mul(1000 * 1000)
+            .saturating_add(u64::from(self.subsec_micros()));
+        i64::try_from(micros).unwrap_or(i64::MAX)
+    }
 }
 
 #[cfg(test)]
@@ -45,4 +56,13 @@ mod test {
         let duration = Duration::new(5, 543_210_000);
         assert_eq!(DurationExtensions::as_micros(&duration), 5_543_210);
     }
+
+    #[test]
+    fn it_saturates_signed_micros_from_durations() {
+        let duration = Duration::new(5, 543_210_000);
+        assert_eq!(DurationExtensions::as_micros_i64(&duration), 5_543_210);
+
+        let huge = Duration::new(u64::MAX, 0);
+        assert_eq!(DurationExtensions::as_micros_i64(&huge), i64::MAX);
+    }
 }
@@ -11,7 +11,7 @@ use crate::player::{Player, DEFAULT_TIMEOUT_MS, MPRIS2_PREFIX};
 use crate::pooled_connection::PooledConnection;
 use crate::PlaybackStatus;
 
-const LIST_NAMES_TIMEOUT_MS: i32 = 500;
+const DEFAULT_LIST_NAMES_TIMEOUT_MS: i32 = 500;
 
 /// This enum encodes possible error cases that could happen when finding players.
 #[derive(Debug, Error)]
@@ -31,6 +31,21 @@ impl From<dbus::Error> for FindingError {
     }
 }
 
+/// Orders that [`PlayerFinder::find_all_sorted_by`] can sort [`Player`]s in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Alphabetical order of the bus names. This is the order used by
+    /// [`PlayerFinder::find_all`].
+    BusName,
+
+    /// Alphabetical order of the [identities](Player::identity), ignoring case.
+    Identity,
+
+    /// [`Playing`](PlaybackStatus::Playing) players first, then
+    /// [`Paused`](PlaybackStatus::Paused) players, then the rest.
+    PlaybackStatus,
+}
+
 /// Used to find [`Player`]s running on a D-Bus connection.
 ///
 /// All find results are sorted in alphabetical order.
@@ -38,6 +53,7 @@ impl From<dbus::Error> for FindingError {
 pub struct PlayerFinder {
     connection: Rc<PooledConnection>,
     player_timeout_ms: i32,
+    list_names_timeout_ms: i32,
 }
 
 impl PlayerFinder {
@@ -58,9 +74,22 @@ impl PlayerFinder {
         PlayerFinder {
             connection: Rc::new(connection.into()),
             player_timeout_ms: DEFAULT_TIMEOUT_MS,
+            list_names_timeout_ms: DEFAULT_LIST_NAMES_TIMEOUT_MS,
         }
     }
 
+    /// Returns the underlying D-Bus connection of this finder, which is shared with all [`Player`]s
+    /// created through it.
+    ///
+    /// Use this to make your own calls or add your own match rules on the same connection.
+    ///
+    /// **Note:** Incoming messages are read from this connection when processing [`Player`]
+    /// events. If you read messages from it yourself, MPRIS signals that you consume will not be
+    /// seen by [`ProgressTracker`](crate::ProgressTracker) or [`PlayerEvents`](crate::PlayerEvents).
+    pub fn connection(&self) -> &Connection {
+        self.connection.underlying()
+    }
+
     /// Get the current timeout value that all [`Player`]s created through this finder will inherit
     ///
     /// Can be set with [`set_player_timeout_ms`][Self::set_player_timeout_ms]
@@ -73,6 +102,40 @@ impl PlayerFinder {
         self.player_timeout_ms = timeout_ms;
     }
 
+    /// Get the timeout, in milliseconds, for listing the names on the bus when looking for
+    /// players. Defaults to 500 ms.
+    ///
+    /// Can be set with [`set_list_names_timeout_ms`][Self::set_list_names_timeout_ms]
+    pub fn list_names_timeout_ms(&self) -> i32 {
+        self.list_names_timeout_ms
+    }
+
+    /// Set the timeout, in milliseconds, for listing the names on the bus when looking for
+    /// players.
+    ///
+    /// Raise this if finding players fails with timeouts on a heavily loaded system.
+    pub fn set_list_names_timeout_ms(&mut self, timeout_ms: i32) {
+        self.list_names_timeout_ms = timeout_ms;
+    }
+
+    /// Get the timeout, in milliseconds, for asking the bus which connection owns a player's bus
+    /// name. Defaults to 100 ms.
+    ///
+    /// Can be set with [`set_name_owner_timeout_ms`][Self::set_name_owner_timeout_ms]
+    pub fn name_owner_timeout_ms(&self) -> i32 {
+        self.connection.name_owner_timeout_ms()
+    }
+
+    /// Set the timeout, in milliseconds, for asking the bus which connection owns a player's bus
+    /// name. This is done when creating [`Player`]s and when checking if they are
+    /// [running](Player::is_running).
+    ///
+    /// The setting is shared with all [`Player`]s that were, or will be, created through this
+    /// finder, as they all use the same connection.
+    pub fn set_name_owner_timeout_ms(&mut self, timeout_ms: i32) {
+        self.connection.set_name_owner_timeout_ms(timeout_ms);
+    }
+
     /// Find all available [`Player`]s in the connection.
     ///
     /// Will return an empty [`Vec`] and not [`NoPlayerFound`](FindingError::NoPlayerFound) if there are no players.
@@ -82,6 +145,54 @@ impl PlayerFinder {
             .collect()
     }
 
+    /// Find all available [`Player`]s in the connection, sorted by the given [`SortKey`]. Players
+    /// that are equal according to the key are sorted by bus name.
+    ///
+    /// Sorting by [`SortKey::PlaybackStatus`] queries the status of every player, so it requires
+    /// an extra D-Bus call per player.
+    pub fn find_all_sorted_by(&self, key: SortKey) -> Result<Vec<Player>, FindingError> {
+        let mut players = self.find_all()?;
+
+        match key {
+            SortKey::BusName => {}
+            SortKey::Identity => {
+                players.sort_by_cached_key(|player| player.identity().to_lowercase());
+            }
+            SortKey::PlaybackStatus => {
+                let mut with_status = players
+                    .into_iter()
+                    .map(|player| Ok((player.get_playback_status()?, player)))
+                    .collect::<Result<Vec<_>, DBusError>>()?;
+
+                with_status.sort_by_key(|(status, _)| match status {
+                    PlaybackStatus::Playing => 0,
+                    PlaybackStatus::Paused => 1,
+                    PlaybackStatus::Stopped => 2,
+                });
+                players = with_status.into_iter().map(|(_, player)| player).collect();
+            }
+        }
+
+        Ok(players)
+    }
+
+    /// Find all [`Player`]s that are currently [`Playing`](PlaybackStatus::Playing).
+    ///
+    /// Will return an empty [`Vec`] and not [`NoPlayerFound`](FindingError::NoPlayerFound) if no
+    /// player is playing, just like [`find_all`](Self::find_all).
+    pub fn find_all_playing(&self) -> Result<Vec<Player>, FindingError> {
+        let mut playing = Vec::new();
+
+        for player in self.iter_players()? {
+            let player = player?;
+            if player.get_playback_status()?.is_playing() {
+                playing.push(player);
+            }
+        }
+
+        Ok(playing)
+    }
+
     /// Return the first found [`Player`] regardless of state.
     pub fn find_first(&self) -> Result<Player, FindingError> {
         if let Some(player) = self.iter_players()?.next() {
@@ -100,14 +211,37 @@ impl PlayerFinder {
     pub fn find_active(&self) -> Result<Player, FindingError> {
         let players: PlayerIter = self.iter_players()?;
 
-        match self.find_active_player(players)? {
+        match self.find_active_player(players, true)? {
+            Some(player) => Ok(player),
+            None => Err(FindingError::NoPlayerFound),
+        }
+    }
+
+    /// Try to find the "active" [`Player`] in the connection, ignoring players that are
+    /// [`Stopped`](PlaybackStatus::Stopped).
+    ///
+    /// This works like [`find_active`](Self::find_active), except that it never falls back to a
+    /// stopped player. [`NoPlayerFound`](FindingError::NoPlayerFound) is returned if no player is
+    /// [`Playing`](PlaybackStatus::Playing) or [`Paused`](PlaybackStatus::Paused).
+    ///
+    /// This is useful for things like status bars, where a stopped player should not be shown.
+    pub fn find_active_not_stopped(&self) -> Result<Player, FindingError> {
+        let players: PlayerIter = self.iter_players()?;
+
+        match self.find_active_player(players, false)? {
             Some(player) => Ok(player),
             None => Err(FindingError::NoPlayerFound),
         }
     }
 
     /// Finds the index of an "active" player. Follows the order mentioned in [`find_active`](Self::find_active).
-    fn find_active_player(&self, players: PlayerIter) -> Result<Option<Player>, DBusError> {
+    ///
+    /// Stopped players are only considered if `include_stopped` is `true`.
+    fn find_active_player(
+        &self,
+        players: PlayerIter,
+        include_stopped: bool,
+    ) -> Result<Option<Player>, DBusError> {
         if players.len() == 0 {
             return Ok(None);
         }
@@ -120,13 +254,19 @@ impl PlayerFinder {
             let player = player?;
             let player_status = player.get_playback_status()?;
 
-            if player_status == PlaybackStatus::Playing {
+            if player_status.is_playing() {
                 return Ok(Some(player));
             }
 
-            if first_paused.is_none() && player_status == PlaybackStatus::Paused {
+            if first_paused.is_none() && player_status.is_paused() {
                 first_paused.replace(player);
-            } else if first_with_track.is_none() && !player.get_metadata()?.is_empty() {
+            } else if !include_stopped {
+                continue;
+            } else if first_with_track.is_none()
+                && player
+                    .checked_get_metadata()?
+                    .map_or(false, |metadata| !metadata.is_empty())
+            {
                 first_with_track.replace(player);
             } else if first_found.is_none() {
                 first_found.replace(player);
@@ -149,6 +289,34 @@ impl PlayerFinder {
         Err(FindingError::NoPlayerFound)
     }
 
+    /// Find the first [`Player`] for which `predicate` returns `Ok(true)`. Returns
+    /// [`NoPlayerFound`](FindingError::NoPlayerFound) if there is no such player.
+    ///
+    /// Players are checked in the same order as [`iter_players`](Self::iter_players), and no more
+    /// players are created once one matches. Errors returned by `predicate` are returned right
+    /// away.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use mpris::PlayerFinder;
+    ///
+    /// let finder = PlayerFinder::new().unwrap();
+    /// let player = finder.find_first_matching(|player| player.can_seek());
+    /// ```
+    pub fn find_first_matching<F>(&self, predicate: F) -> Result<Player, FindingError>
+    where
+        F: Fn(&Player) -> Result<bool, DBusError>,
+    {
+        for player in self.iter_players()? {
+            let player = player?;
+            if predicate(&player)? {
+                return Ok(player);
+            }
+        }
+        Err(FindingError::NoPlayerFound)
+    }
+
     /// Returns all of the MPRIS DBus paths
     fn all_player_buses(&self) -> Result<Vec<String>, DBusError> {
         let list_names = Message::new_method_call(
@@ -162,7 +330,7 @@ impl PlayerFinder {
         let reply = self
             .connection
             .underlying()
-            .send_with_reply_and_block(list_names, LIST_NAMES_TIMEOUT_MS)?;
+            .send_with_reply_and_block(list_names, self.list_names_timeout_ms)?;
 
         let names: arg::Array<'_, &str, _> = reply.read1().map_err(DBusError::from)?;
 
@@ -27,6 +27,7 @@
 //!
 //! [spec]: https://specifications.freedesktop.org/mpris-spec/latest/
 
+use std::convert::TryFrom;
 use thiserror::Error;
 
 mod extensions;
@@ -38,18 +39,26 @@ mod event;
 mod find;
 mod metadata;
 mod player;
+mod playlist;
 mod pooled_connection;
 mod progress;
+mod retry;
+#[cfg(test)]
+mod test_player;
 mod track_list;
 
 pub use crate::event::{Event, EventError, PlayerEvents};
-pub use crate::find::{FindingError, PlayerFinder, PlayerIter};
+pub use crate::find::{FindingError, PlayerFinder, PlayerIter, SortKey};
 pub use crate::metadata::Metadata;
 pub use crate::metadata::Value as MetadataValue;
 pub use crate::metadata::ValueKind as MetadataValueKind;
-pub use crate::player::Player;
-pub use crate::progress::{Progress, ProgressError, ProgressTick, ProgressTracker};
-pub use crate::track_list::{TrackID, TrackList, TrackListError};
+pub use crate::player::{AddTracksError, NowPlaying, Player};
+pub use crate::playlist::{Playlist, PlaylistOrdering};
+pub use crate::progress::{
+    MultiProgressTracker, Progress, ProgressError, ProgressTick, ProgressTracker,
+};
+pub use crate::retry::retry;
+pub use crate::track_list::{TrackID, TrackList, TrackListError, TrackListOperation};
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[allow(missing_docs)]
@@ -67,6 +76,32 @@ pub enum PlaybackStatus {
     Stopped,
 }
 
+impl PlaybackStatus {
+    /// Returns the exact string used by MPRIS for this status, like `"Playing"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PlaybackStatus::Playing => "Playing",
+            PlaybackStatus::Paused => "Paused",
+            PlaybackStatus::Stopped => "Stopped",
+        }
+    }
+
+    /// Returns [`true`] if the status is [`Playing`](PlaybackStatus::Playing).
+    pub fn is_playing(self) -> bool {
+        self == PlaybackStatus::Playing
+    }
+
+    /// Returns [`true`] if the status is [`Paused`](PlaybackStatus::Paused).
+    pub fn is_paused(self) -> bool {
+        self == PlaybackStatus::Paused
+    }
+
+    /// Returns [`true`] if the status is [`Stopped`](PlaybackStatus::Stopped).
+    pub fn is_stopped(self) -> bool {
+        self == PlaybackStatus::Stopped
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 /// A [`Player`]'s looping status.
 ///
@@ -89,6 +124,13 @@ pub enum LoopStatus {
 #[error("PlaybackStatus must be one of Playing, Paused, Stopped, but was {0}")]
 pub struct InvalidPlaybackStatus(String);
 
+impl InvalidPlaybackStatus {
+    /// The value that the [`Player`] sent, which could not be parsed.
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
 impl ::std::str::FromStr for PlaybackStatus {
     type Err = InvalidPlaybackStatus;
 
@@ -104,11 +146,33 @@ impl ::std::str::FromStr for PlaybackStatus {
     }
 }
 
+impl TryFrom<&str> for PlaybackStatus {
+    type Error = InvalidPlaybackStatus;
+
+    fn try_from(string: &str) -> Result<Self, Self::Error> {
+        string.parse()
+    }
+}
+
+/// Formats the status as the exact string used by MPRIS, like `Playing`.
+impl ::std::fmt::Display for PlaybackStatus {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// [`LoopStatus`] had an invalid string value.
 #[derive(Debug, Error)]
 #[error("LoopStatus must be one of None, Track, Playlist, but was {0}")]
 pub struct InvalidLoopStatus(String);
 
+impl InvalidLoopStatus {
+    /// The value that the [`Player`] sent, which could not be parsed.
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
 impl ::std::str::FromStr for LoopStatus {
     type Err = InvalidLoopStatus;
 
@@ -122,9 +186,24 @@ impl ::std::str::FromStr for LoopStatus {
     }
 }
 
+impl TryFrom<&str> for LoopStatus {
+    type Error = InvalidLoopStatus;
+
+    fn try_from(string: &str) -> Result<Self, Self::Error> {
+        string.parse()
+    }
+}
+
 impl LoopStatus {
     fn dbus_value(self) -> String {
-        String::from(match self {
+        self.to_string()
+    }
+}
+
+/// Formats the status as the exact string used by MPRIS, like `Playlist`.
+impl ::std::fmt::Display for LoopStatus {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        f.write_str(match self {
             LoopStatus::None => "None",
             LoopStatus::Track => "Track",
             LoopStatus::Playlist => "Playlist",
@@ -155,6 +234,73 @@ pub enum DBusError {
     Miscellaneous(String),
 }
 
+impl DBusError {
+    /// Returns [`true`] if this error indicates that the [`Player`] is no longer running on the
+    /// bus, for example because it quit.
+    ///
+    /// This is the case for the D-Bus errors `org.freedesktop.DBus.Error.ServiceUnknown`,
+    /// `org.freedesktop.DBus.Error.NameHasNoOwner`, `org.freedesktop.DBus.Error.Disconnected` and
+    /// `org.freedesktop.DBus.Error.NoReply`.
+    ///
+    /// You can use this to decide when to start looking for a new player.
+    pub fn is_player_gone(&self) -> bool {
+        match self {
+            DBusError::TransportError(error) => matches!(
+                error.name(),
+                Some("org.freedesktop.DBus.Error.ServiceUnknown")
+                    | Some("org.freedesktop.DBus.Error.NameHasNoOwner")
+                    | Some("org.freedesktop.DBus.Error.Disconnected")
+                    | Some("org.freedesktop.DBus.Error.NoReply")
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns [`true`] if this error means that the [`Player`] does not support what was asked
+    /// for, rather than that the call failed.
+    ///
+    /// This is the case for the D-Bus errors `org.freedesktop.DBus.Error.UnknownProperty`,
+    /// `org.freedesktop.DBus.Error.UnknownInterface`, `org.freedesktop.DBus.Error.UnknownMethod`
+    /// and `org.freedesktop.DBus.Error.NotSupported`.
+    ///
+    /// You can use this to fall back gracefully when a player does not implement an optional
+    /// part of MPRIS.
+    pub fn is_not_supported(&self) -> bool {
+        match self {
+            DBusError::TransportError(error) => matches!(
+                error.name(),
+                Some("org.freedesktop.DBus.Error.UnknownProperty")
+                    | Some("org.freedesktop.DBus.Error.UnknownInterface")
+                    | Some("org.freedesktop.DBus.Error.UnknownMethod")
+                    | Some("org.freedesktop.DBus.Error.NotSupported")
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns [`true`] if this error is likely to be temporary, so that trying again later might
+    /// succeed.
+    ///
+    /// This is the case for the D-Bus errors `org.freedesktop.DBus.Error.NoReply`,
+    /// `org.freedesktop.DBus.Error.Timeout`, `org.freedesktop.DBus.Error.TimedOut` and
+    /// `org.freedesktop.DBus.Error.LimitsExceeded`. Note that a player that has quit can also
+    /// cause `NoReply`; see [`is_player_gone`](Self::is_player_gone).
+    ///
+    /// See [`retry`] for a helper that retries calls failing with transient errors.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            DBusError::TransportError(error) => matches!(
+                error.name(),
+                Some("org.freedesktop.DBus.Error.NoReply")
+                    | Some("org.freedesktop.DBus.Error.Timeout")
+                    | Some("org.freedesktop.DBus.Error.TimedOut")
+                    | Some("org.freedesktop.DBus.Error.LimitsExceeded")
+            ),
+            _ => false,
+        }
+    }
+}
+
 impl From<InvalidPlaybackStatus> for DBusError {
     fn from(error: InvalidPlaybackStatus) -> Self {
         DBusError::EnumParseError(error.to_string())
@@ -166,3 +312,100 @@ impl From<InvalidLoopStatus> for DBusError {
         DBusError::EnumParseError(error.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transport_error(name: &str) -> DBusError {
+        DBusError::TransportError(dbus::Error::new_custom(name, "Test error"))
+    }
+
+    #[test]
+    fn it_detects_players_that_are_gone() {
+        assert!(transport_error("org.freedesktop.DBus.Error.ServiceUnknown").is_player_gone());
+        assert!(transport_error("org.freedesktop.DBus.Error.NameHasNoOwner").is_player_gone());
+        assert!(transport_error("org.freedesktop.DBus.Error.Disconnected").is_player_gone());
+        assert!(transport_error("org.freedesktop.DBus.Error.NoReply").is_player_gone());
+
+        assert!(!transport_error("org.freedesktop.DBus.Error.InvalidArgs").is_player_gone());
+        assert!(!DBusError::Miscellaneous(String::from("Gone")).is_player_gone());
+    }
+
+    #[test]
+    fn it_detects_unsupported_features() {
+        assert!(transport_error("org.freedesktop.DBus.Error.UnknownProperty").is_not_supported());
+        assert!(transport_error("org.freedesktop.DBus.Error.UnknownInterface").is_not_supported());
+        assert!(transport_error("org.freedesktop.DBus.Error.UnknownMethod").is_not_supported());
+        assert!(transport_error("org.freedesktop.DBus.Error.NotSupported").is_not_supported());
+
+        assert!(!transport_error("org.freedesktop.DBus.Error.NoReply").is_not_supported());
+        assert!(!DBusError::Miscellaneous(String::from("Unsupported")).is_not_supported());
+    }
+
+    #[test]
+    fn it_keeps_invalid_status_values() {
+        assert_eq!(
+            PlaybackStatus::try_from("Paused").unwrap(),
+            PlaybackStatus::Paused
+        );
+        assert_eq!(
+            PlaybackStatus::try_from("paused").unwrap_err().value(),
+            "paused"
+        );
+
+        assert_eq!(LoopStatus::try_from("Track").unwrap(), LoopStatus::Track);
+        assert_eq!(LoopStatus::try_from("All").unwrap_err().value(), "All");
+    }
+
+    #[test]
+    fn it_detects_transient_errors() {
+        assert!(transport_error("org.freedesktop.DBus.Error.NoReply").is_transient());
+        assert!(transport_error("org.freedesktop.DBus.Error.Timeout").is_transient());
+        assert!(transport_error("org.freedesktop.DBus.Error.TimedOut").is_transient());
+        assert!(transport_error("org.freedesktop.DBus.Error.LimitsExceeded").is_transient());
+
+        assert!(!transport_error("org.freedesktop.DBus.Error.ServiceUnknown").is_transient());
+        assert!(!DBusError::Miscellaneous(String::from("Timeout")).is_transient());
+    }
+
+    #[test]
+    fn it_round_trips_statuses_through_strings() {
+        for status in &[
+            PlaybackStatus::Playing,
+            PlaybackStatus::Paused,
+            PlaybackStatus::Stopped,
+        ] {
+            assert_eq!(
+                status.to_string().parse::<PlaybackStatus>().unwrap(),
+                *status
+            );
+        }
+        for status in &[LoopStatus::None, LoopStatus::Track, LoopStatus::Playlist] {
+            assert_eq!(status.to_string().parse::<LoopStatus>().unwrap(), *status);
+        }
+
+        assert_eq!(PlaybackStatus::Playing.to_string(), "Playing");
+        assert_eq!(PlaybackStatus::Stopped.as_str(), "Stopped");
+        assert!(PlaybackStatus::Paused.is_paused());
+        assert!(!PlaybackStatus::Paused.is_playing());
+        assert_eq!(
+            "Buffering"
+                .parse::<PlaybackStatus>()
+                .unwrap_err()
+                .to_string(),
+            "PlaybackStatus must be one of Playing, Paused, Stopped, but was Buffering"
+        );
+        assert_eq!(
+            "".parse::<LoopStatus>().unwrap_err().to_string(),
+            "LoopStatus must be one of None, Track, Playlist, but was "
+        );
+    }
+
+    #[test]
+    fn it_formats_loop_status_as_mpris_strings() {
+        assert_eq!(LoopStatus::None.to_string(), "None");
+        assert_eq!(LoopStatus::Track.to_string(), "Track");
+        assert_eq!(LoopStatus::Playlist.to_string(), "Playlist");
+    }
+}
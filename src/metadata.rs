@@ -3,6 +3,7 @@ pub use self::value::{Value, ValueKind};
 use super::TrackID;
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::Duration;
 
 /// A structured representation of the [`Player`](crate::player::Player) metadata.
@@ -12,7 +13,7 @@ use std::time::Duration;
 ///
 /// [metadata_map]: https://specifications.freedesktop.org/mpris-spec/latest/Track_List_Interface.html#Mapping:Metadata_Map
 /// [metadata_guidelines]: https://www.freedesktop.org/wiki/Specifications/mpris-spec/metadata/
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Metadata {
     values: HashMap<String, Value>,
 }
@@ -51,9 +52,65 @@ impl Metadata {
         self.values.get(key)
     }
 
+    /// Get a value from the metadata, tolerating players that use inconsistent casing or leave out
+    /// the standard `xesam:` and `mpris:` prefixes.
+    ///
+    /// The following keys are tried in order, and the first one that is found is returned:
+    ///
+    /// 1. `key` as given.
+    /// 2. If `key` has no prefix, `xesam:` and then `mpris:` followed by `key`.
+    /// 3. If `key` starts with `xesam:` or `mpris:`, `key` without the prefix.
+    ///
+    /// Each of these is first looked up exactly and then case-insensitively. If several keys
+    /// match case-insensitively, the one that sorts first is used.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use mpris::{Metadata, MetadataValue};
+    /// # use std::collections::HashMap;
+    /// let mut values = HashMap::new();
+    /// values.insert(String::from("xesam:Artist"), MetadataValue::from("Agnes Obel"));
+    /// values.insert(String::from("Album"), MetadataValue::from("Aventine"));
+    /// let metadata = Metadata::from(values);
+    ///
+    /// assert_eq!(metadata.get_ci("xesam:artist"), Some(&MetadataValue::from("Agnes Obel")));
+    /// assert_eq!(metadata.get_ci("artist"), Some(&MetadataValue::from("Agnes Obel")));
+    /// assert_eq!(metadata.get_ci("xesam:album"), Some(&MetadataValue::from("Aventine")));
+    /// ```
+    pub fn get_ci(&self, key: &str) -> Option<&Value> {
+        const PREFIXES: [&str; 2] = ["xesam:", "mpris:"];
+
+        let mut candidates = vec![key.to_string()];
+        match PREFIXES
+            .iter()
+            .find(|prefix| starts_with_ignore_ascii_case(key, prefix))
+        {
+            Some(prefix) => candidates.push(key[prefix.len()..].to_string()),
+            None => candidates.extend(PREFIXES.iter().map(|prefix| format!("{}{}", prefix, key))),
+        }
+
+        candidates.iter().find_map(|candidate| {
+            self.values.get(candidate).or_else(|| {
+                self.values
+                    .iter()
+                    .filter(|(k, _)| k.eq_ignore_ascii_case(candidate))
+                    .min_by(|(a, _), (b, _)| a.cmp(b))
+                    .map(|(_, value)| value)
+            })
+        })
+    }
+
     /// The track ID.
     ///
     /// If the [`TrackID`] could not be parsed as a proper [`TrackID`], [`None`] will be returned.
+    /// Some players put strings that are not valid D-Bus object paths here, against the
+    /// specification. Those IDs would not work with methods like
+    /// [`Player::go_to`](crate::Player::go_to) or
+    /// [`Player::set_position`](crate::Player::set_position) anyway, so they are also reported as
+    /// [`None`].
+    ///
+    /// Use [`get`](Self::get) if you need to read the raw value.
     ///
     /// Based on `mpris:trackid`
     /// > A unique identity for this track within the context of an MPRIS object.
@@ -89,6 +146,15 @@ impl Metadata {
         self.get("mpris:artUrl").and_then(Value::as_str)
     }
 
+    /// The [`art_url`](Self::art_url) as a local filesystem path, if it is a `file:` URL.
+    ///
+    /// Percent-encoded characters, like `%20` for spaces, are decoded. Returns `None` for any
+    /// other kind of URL, like `http:`, `https:` or `data:` URLs, and for `file:` URLs that point
+    /// to another host.
+    pub fn art_url_as_path(&self) -> Option<PathBuf> {
+        self.art_url().and_then(file_url_to_path)
+    }
+
     /// A list of artists of the track.
     ///
     /// Based on `xesam:artist`
@@ -110,6 +176,20 @@ impl Metadata {
         self.get("xesam:discNumber").and_then(Value::as_i32)
     }
 
+    /// The first of the track's [`artists`](Self::artists).
+    pub fn first_artist(&self) -> Option<&str> {
+        self.artists()
+            .and_then(|artists| artists.into_iter().next())
+    }
+
+    /// A list of genres of the track.
+    ///
+    /// Based on `xesam:genre`
+    /// > List of Strings.
+    pub fn genres(&self) -> Option<Vec<&str>> {
+        self.get("xesam:genre").and_then(Value::as_str_array)
+    }
+
     /// The duration of the track, in microseconds
     ///
     /// Based on `mpris:length`
@@ -131,6 +211,28 @@ impl Metadata {
         self.length_in_microseconds().map(Duration::from_micros_ext)
     }
 
+    /// The [`auto_rating`](Self::auto_rating), normalized into the range 0.0 to 1.0.
+    ///
+    /// See [`normalized_user_rating`](Self::normalized_user_rating) for how values outside of
+    /// the range are handled.
+    pub fn normalized_auto_rating(&self) -> Option<f64> {
+        self.auto_rating().and_then(normalize_rating)
+    }
+
+    /// The [`user_rating`](Self::user_rating), normalized into the range 0.0 to 1.0.
+    ///
+    /// Not all players follow the specification here. Values above 1.0 and up to 5.0 are treated
+    /// as being on a scale of 0 to 5 (like stars), and larger values as being on a scale of 0 to
+    /// 100. The result is clamped into the range 0.0 to 1.0.
+    pub fn normalized_user_rating(&self) -> Option<f64> {
+        self.user_rating().and_then(normalize_rating)
+    }
+
+    /// The first of the track's [`genres`](Self::genres).
+    pub fn primary_genre(&self) -> Option<&str> {
+        self.genres().and_then(|genres| genres.into_iter().next())
+    }
+
     /// The name of the track.
     ///
     /// Based on `xesam:title`
@@ -155,6 +257,12 @@ impl Metadata {
         self.get("xesam:url").and_then(Value::as_str)
     }
 
+    /// Based on `xesam:userRating`
+    /// > A user-specified rating. This should be in the range 0.0 to 1.0.
+    pub fn user_rating(&self) -> Option<f64> {
+        self.get("xesam:userRating").and_then(Value::as_f64)
+    }
+
     /// Returns an owned [`HashMap`] of borrowed values from this [`Metadata`]. Useful if you need a
     /// mutable hash but don't have ownership of [`Metadata`] or want to consume it.
     ///
@@ -173,12 +281,46 @@ impl Metadata {
         self.values.keys().map(String::as_str)
     }
 
+    /// Iterate the metadata keys and values that are not part of the [MPRIS v2 metadata
+    /// guidelines][metadata_guidelines], like vendor-specific keys added by some players.
+    ///
+    /// [metadata_guidelines]: https://www.freedesktop.org/wiki/Specifications/mpris-spec/metadata/
+    pub fn extension_keys(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.iter().filter(|(key, _)| !KNOWN_KEYS.contains(key))
+    }
+
     /// Returns [`true`] if there is no metadata
     pub fn is_empty(&self) -> bool {
         self.values.is_empty()
     }
 }
 
+/// Keys defined by the MPRIS v2 metadata guidelines.
+const KNOWN_KEYS: &[&str] = &[
+    "mpris:trackid",
+    "mpris:length",
+    "mpris:artUrl",
+    "xesam:album",
+    "xesam:albumArtist",
+    "xesam:artist",
+    "xesam:asText",
+    "xesam:audioBPM",
+    "xesam:autoRating",
+    "xesam:comment",
+    "xesam:composer",
+    "xesam:contentCreated",
+    "xesam:discNumber",
+    "xesam:firstUsed",
+    "xesam:genre",
+    "xesam:lastUsed",
+    "xesam:lyricist",
+    "xesam:title",
+    "xesam:trackNumber",
+    "xesam:url",
+    "xesam:useCount",
+    "xesam:userRating",
+];
+
 impl IntoIterator for Metadata {
     type Item = (String, Value);
     type IntoIter = std::collections::hash_map::IntoIter<String, Value>;
@@ -203,6 +345,72 @@ impl From<HashMap<String, Value>> for Metadata {
     }
 }
 
+fn starts_with_ignore_ascii_case(value: &str, prefix: &str) -> bool {
+    value
+        .get(..prefix.len())
+        .map_or(false, |start| start.eq_ignore_ascii_case(prefix))
+}
+
+fn normalize_rating(rating: f64) -> Option<f64> {
+    if rating.is_nan() {
+        None
+    } else if rating <= 1.0 {
+        Some(rating.max(0.0))
+    } else if rating <= 5.0 {
+        Some(rating / 5.0)
+    } else {
+        Some((rating / 100.0).min(1.0))
+    }
+}
+
+/// Converts a `file:` URL into a local path, decoding any percent-encoded characters.
+fn file_url_to_path(url: &str) -> Option<PathBuf> {
+    let scheme_end = url.find(':')?;
+    if !url[..scheme_end].eq_ignore_ascii_case("file") {
+        return None;
+    }
+
+    let rest = &url[scheme_end + 1..];
+    let path = if let Some(authority_and_path) = rest.strip_prefix("//") {
+        let path_start = authority_and_path.find('/')?;
+        match &authority_and_path[..path_start] {
+            "" | "localhost" => &authority_and_path[path_start..],
+            _ => return None,
+        }
+    } else {
+        rest
+    };
+
+    // Queries and fragments are not part of the path.
+    let path = path.split(|c| c == '?' || c == '#').next().unwrap_or("");
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    percent_decode(path)
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .map(PathBuf::from)
+}
+
+fn percent_decode(input: &str) -> Option<Vec<u8>> {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = input.get(i + 1..i + 3)?;
+            output.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            output.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    Some(output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,6 +421,16 @@ mod tests {
         assert_eq!(metadata.track_id(), Some(TrackID::new("/foo").unwrap()));
     }
 
+    #[test]
+    fn it_ignores_track_ids_that_are_not_object_paths() {
+        let metadata = Metadata::new("spotify:track:1234");
+        assert_eq!(metadata.track_id(), None);
+        assert_eq!(
+            metadata.get("mpris:trackid").and_then(Value::as_str),
+            Some("spotify:track:1234")
+        );
+    }
+
     #[test]
     fn it_supports_blank_metadata() {
         let metadata = Metadata::from(HashMap::new());
@@ -274,4 +492,104 @@ mod tests {
 
         assert_eq!(metadata.artists(), Some(vec!["Agnes Obel"]));
     }
+
+    #[test]
+    fn it_lists_extension_keys() {
+        let mut values = HashMap::new();
+        values.insert(String::from("xesam:title"), Value::from("Song"));
+        values.insert(String::from("mpris:trackid"), Value::from("/foo"));
+        values.insert(String::from("vendor:flag"), Value::Bool(true));
+        let metadata = Metadata::from(values);
+
+        let extensions: Vec<_> = metadata.extension_keys().collect();
+        assert_eq!(extensions, vec![("vendor:flag", &Value::Bool(true))]);
+    }
+
+    #[test]
+    fn it_returns_the_first_artist_and_genre() {
+        let mut values = HashMap::new();
+        values.insert(
+            String::from("xesam:artist"),
+            Value::Array(vec![Value::from("First"), Value::from("Second")]),
+        );
+        values.insert(String::from("xesam:genre"), Value::from("Rock"));
+        let metadata = Metadata::from(values);
+
+        assert_eq!(metadata.first_artist(), Some("First"));
+        assert_eq!(metadata.primary_genre(), Some("Rock"));
+        assert_eq!(Metadata::new("/foo").first_artist(), None);
+    }
+
+    #[test]
+    fn it_converts_local_art_urls_to_paths() {
+        let with_art_url = |url: &str| {
+            let mut metadata = Metadata::new("/foo");
+            metadata
+                .values
+                .insert(String::from("mpris:artUrl"), Value::from(url));
+            metadata
+        };
+
+        assert_eq!(
+            with_art_url("file:///home/me/My%20Music/cover.jpg").art_url_as_path(),
+            Some(PathBuf::from("/home/me/My Music/cover.jpg"))
+        );
+        assert_eq!(
+            with_art_url("FILE://localhost/tmp/%C3%A5.png").art_url_as_path(),
+            Some(PathBuf::from("/tmp/\u{e5}.png"))
+        );
+        assert_eq!(
+            with_art_url("file:/tmp/cover.png").art_url_as_path(),
+            Some(PathBuf::from("/tmp/cover.png"))
+        );
+        assert_eq!(
+            with_art_url("https://example.com/cover.png").art_url_as_path(),
+            None
+        );
+        assert_eq!(
+            with_art_url("data:image/png;base64,AAAA").art_url_as_path(),
+            None
+        );
+        assert_eq!(
+            with_art_url("file://example.com/cover.png").art_url_as_path(),
+            None
+        );
+        assert_eq!(with_art_url("file:///bad%2").art_url_as_path(), None);
+        assert_eq!(Metadata::new("/foo").art_url_as_path(), None);
+    }
+
+    #[test]
+    fn it_normalizes_ratings() {
+        assert_eq!(normalize_rating(0.5), Some(0.5));
+        assert_eq!(normalize_rating(1.0), Some(1.0));
+        assert_eq!(normalize_rating(-0.5), Some(0.0));
+        assert_eq!(normalize_rating(4.0), Some(0.8));
+        assert_eq!(normalize_rating(80.0), Some(0.8));
+        assert_eq!(normalize_rating(250.0), Some(1.0));
+        assert_eq!(normalize_rating(f64::NAN), None);
+
+        let mut metadata = Metadata::new("/foo");
+        metadata
+            .values
+            .insert(String::from("xesam:userRating"), Value::F64(3.0));
+        assert_eq!(metadata.user_rating(), Some(3.0));
+        assert_eq!(metadata.normalized_user_rating(), Some(0.6));
+        assert_eq!(metadata.normalized_auto_rating(), None);
+    }
+
+    #[test]
+    fn it_looks_up_keys_leniently() {
+        let mut values = HashMap::new();
+        values.insert(String::from("xesam:title"), Value::from("exact"));
+        values.insert(String::from("XESAM:TITLE"), Value::from("upper"));
+        values.insert(String::from("Xesam:Title"), Value::from("mixed"));
+        values.insert(String::from("length"), Value::U64(1));
+        let metadata = Metadata::from(values);
+
+        assert_eq!(metadata.get_ci("xesam:title"), Some(&Value::from("exact")));
+        assert_eq!(metadata.get_ci("xesam:TITLE"), Some(&Value::from("upper")));
+        assert_eq!(metadata.get_ci("title"), Some(&Value::from("exact")));
+        assert_eq!(metadata.get_ci("mpris:Length"), Some(&Value::U64(1)));
+        assert_eq!(metadata.get_ci("mpris:trackid"), None);
+    }
 }
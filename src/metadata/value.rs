@@ -1,7 +1,11 @@
-use dbus::arg::ArgType;
+use dbus::arg::{ArgType, RefArg, Variant};
 use enum_kinds::EnumKind;
 use from_variants::FromVariants;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::extensions::DurationExtensions;
 
 /// Holds a dynamically-typed metadata value.
 ///
@@ -94,6 +98,43 @@ impl Value {
         }
     }
 
+    /// Returns the value as a `Some(Vec<i64>)` if it is a `MetadataValue::Array`. Elements are
+    /// converted like [`as_i64`](Self::as_i64), with the addition of unsigned integers that fit in
+    /// an `i64`. Any elements that are not integers will be ignored.
+    ///
+    /// A single integer value is returned as a `Vec` with one element, just like
+    /// [`as_str_array`](Self::as_str_array) does for strings.
+    pub fn as_i64_array(&self) -> Option<Vec<i64>> {
+        match *self {
+            Value::Array(ref vec) => Some(vec.iter().flat_map(Value::as_lossless_i64).collect()),
+            _ => self.as_lossless_i64().map(|val| vec![val]),
+        }
+    }
+
+    /// Returns the value as a `Some(Vec<f64>)` if it is a `MetadataValue::Array`. Integer elements
+    /// are converted to `f64`, and any elements that are not numbers will be ignored.
+    ///
+    /// A single numeric value is returned as a `Vec` with one element.
+    pub fn as_f64_array(&self) -> Option<Vec<f64>> {
+        match *self {
+            Value::Array(ref vec) => Some(vec.iter().flat_map(Value::as_lossy_f64).collect()),
+            _ => self.as_lossy_f64().map(|val| vec![val]),
+        }
+    }
+
+    fn as_lossless_i64(&self) -> Option<i64> {
+        self.as_i64()
+            .or_else(|| self.as_u64().and_then(|val| i64::try_from(val).ok()))
+    }
+
+    fn as_lossy_f64(&self) -> Option<f64> {
+        match *self {
+            Value::F64(val) => Some(val),
+            Value::U64(val) => Some(val as f64),
+            _ => self.as_lossless_i64().map(|val| val as f64),
+        }
+    }
+
     /// Returns the value as a `Some(u8)` if it is a `MetadataValue::U8`, or `None` otherwise.
     pub fn as_u8(&self) -> Option<u8> {
         match *self {
@@ -173,6 +214,16 @@ impl Value {
         }
     }
 
+    /// Returns the value as a `Some(Duration)` if it is an integer, interpreting it as a number of
+    /// microseconds like `mpris:length`. Returns `None` for negative numbers and non-integers.
+    pub fn as_duration(&self) -> Option<Duration> {
+        let micros = match self.as_u64() {
+            Some(val) => val,
+            None => u64::try_from(self.as_i64()?).ok()?,
+        };
+        Some(Duration::from_micros_ext(micros))
+    }
+
     /// Returns the value as a `Some(bool)` if it is a `MetadataValue::Bool`, or `None` otherwise.
     pub fn as_bool(&self) -> Option<bool> {
         match *self {
@@ -181,6 +232,24 @@ impl Value {
         }
     }
 
+    /// Returns the value as a `Some(bool)` if it is a `MetadataValue::Bool`, or an integer with
+    /// the value `0` (`false`) or `1` (`true`). Returns `None` otherwise.
+    ///
+    /// Some players encode booleans as integers in custom metadata fields. Use
+    /// [`as_bool`](Self::as_bool) if you only want to accept real booleans.
+    pub fn as_bool_lenient(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(val) => Some(val),
+            Value::U64(0) => Some(false),
+            Value::U64(1) => Some(true),
+            _ => match self.as_lossless_i64() {
+                Some(0) => Some(false),
+                Some(1) => Some(true),
+                _ => None,
+            },
+        }
+    }
+
     /// Returns the value as a `Some(&str)` if it is a `MetadataValue::String`, or `None` otherwise.
     pub fn as_str(&self) -> Option<&str> {
         match *self {
@@ -213,6 +282,50 @@ impl Value {
         }
     }
 
+    /// Returns the element at `index` if this is a `MetadataValue::Array`, or `None` if the index
+    /// is out of range or this is not an array.
+    pub fn get_index(&self, index: usize) -> Option<&Value> {
+        self.as_array().and_then(|array| array.get(index))
+    }
+
+    /// Walks nested values, one segment of `path` at a time, and returns the value at the end.
+    ///
+    /// A segment is looked up as a key in a `MetadataValue::Map`, or as an index in a
+    /// `MetadataValue::Array` if it is a number. Returns `None` if any step of the path is
+    /// missing. An empty path returns `self`.
+    ///
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// use mpris::MetadataValue;
+    ///
+    /// let mut extra = HashMap::new();
+    /// extra.insert(String::from("rating"), MetadataValue::U8(4));
+    /// extra.insert(
+    ///     String::from("tags"),
+    ///     MetadataValue::Array(vec![MetadataValue::from("live")]),
+    /// );
+    /// let mut root = HashMap::new();
+    /// root.insert(String::from("extra"), MetadataValue::Map(extra));
+    /// let value = MetadataValue::Map(root);
+    ///
+    /// assert_eq!(value.get_path(&["extra", "rating"]), Some(&MetadataValue::U8(4)));
+    /// assert_eq!(
+    ///     value.get_path(&["extra", "tags", "0"]).and_then(MetadataValue::as_str),
+    ///     Some("live")
+    /// );
+    /// assert_eq!(value.get_path(&["extra", "missing"]), None);
+    /// ```
+    pub fn get_path(&self, path: &[&str]) -> Option<&Value> {
+        path.iter().try_fold(self, |value, segment| match *value {
+            Value::Map(ref map) => map.get(*segment),
+            Value::Array(_) => segment
+                .parse()
+                .ok()
+                .and_then(|index| value.get_index(index)),
+            _ => None,
+        })
+    }
+
     /// Consumes `self` and returns the inner value as a `Some(u8)` if it is a `MetadataValue::U8`, or `None` otherwise.
     pub fn into_u8(self) -> Option<u8> {
         match self {
@@ -330,6 +443,81 @@ impl Value {
     }
 }
 
+impl Value {
+    /// Renders the value as a human-readable string, putting each element of nested arrays and
+    /// maps on its own line, indented by `indent` spaces per level of nesting.
+    ///
+    /// This is meant for debugging; the output format is not stable. Map keys are sorted to keep
+    /// the output deterministic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mpris::MetadataValue;
+    /// let value = MetadataValue::Array(vec![
+    ///     MetadataValue::from("Artist"),
+    ///     MetadataValue::I32(42),
+    /// ]);
+    /// assert_eq!(value.to_pretty_string(2), "[\n  \"Artist\",\n  42\n]");
+    /// ```
+    pub fn to_pretty_string(&self, indent: usize) -> String {
+        let mut output = String::new();
+        self.write_pretty(&mut output, indent, 0);
+        output
+    }
+
+    fn write_pretty(&self, output: &mut String, indent: usize, depth: usize) {
+        let inner_padding = " ".repeat(indent * (depth + 1));
+        let closing_padding = " ".repeat(indent * depth);
+
+        match self {
+            Value::Array(values) if values.is_empty() => output.push_str("[]"),
+            Value::Array(values) => {
+                output.push_str("[\n");
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        output.push_str(",\n");
+                    }
+                    output.push_str(&inner_padding);
+                    value.write_pretty(output, indent, depth + 1);
+                }
+                output.push('\n');
+                output.push_str(&closing_padding);
+                output.push(']');
+            }
+            Value::Map(map) if map.is_empty() => output.push_str("{}"),
+            Value::Map(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+
+                output.push_str("{\n");
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        output.push_str(",\n");
+                    }
+                    output.push_str(&inner_padding);
+                    output.push_str(&format!("{:?}: ", key));
+                    value.write_pretty(output, indent, depth + 1);
+                }
+                output.push('\n');
+                output.push_str(&closing_padding);
+                output.push('}');
+            }
+            Value::String(value) => output.push_str(&format!("{:?}", value)),
+            Value::I16(value) => output.push_str(&value.to_string()),
+            Value::I32(value) => output.push_str(&value.to_string()),
+            Value::I64(value) => output.push_str(&value.to_string()),
+            Value::U8(value) => output.push_str(&value.to_string()),
+            Value::U16(value) => output.push_str(&value.to_string()),
+            Value::U32(value) => output.push_str(&value.to_string()),
+            Value::U64(value) => output.push_str(&value.to_string()),
+            Value::F64(value) => output.push_str(&format!("{:?}", value)),
+            Value::Bool(value) => output.push_str(&value.to_string()),
+            Value::Unsupported => output.push_str("<unsupported>"),
+        }
+    }
+}
+
 impl Value {
     /// Returns true if `self` is a String
     pub fn is_string(&self) -> bool {
@@ -397,6 +585,48 @@ impl Value {
     }
 }
 
+impl Value {
+    /// Converts the value into something that can be sent over D-Bus inside of a variant.
+    ///
+    /// Arrays of strings are sent as `as`, and other arrays as arrays of variants. Returns
+    /// [`None`] for `MetadataValue::Unsupported`, or for arrays and maps containing it.
+    pub(crate) fn to_ref_arg(&self) -> Option<Box<dyn RefArg>> {
+        Some(match *self {
+            Value::String(ref val) => Box::new(val.clone()),
+            Value::I16(val) => Box::new(val),
+            Value::I32(val) => Box::new(val),
+            Value::I64(val) => Box::new(val),
+            Value::U8(val) => Box::new(val),
+            Value::U16(val) => Box::new(val),
+            Value::U32(val) => Box::new(val),
+            Value::U64(val) => Box::new(val),
+            Value::F64(val) => Box::new(val),
+            Value::Bool(val) => Box::new(val),
+            Value::Array(ref values) => match values
+                .iter()
+                .map(Value::as_string)
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(strings) if !strings.is_empty() => {
+                    Box::new(strings.into_iter().cloned().collect::<Vec<String>>())
+                }
+                _ => Box::new(
+                    values
+                        .iter()
+                        .map(|value| value.to_ref_arg().map(Variant))
+                        .collect::<Option<Vec<_>>>()?,
+                ),
+            },
+            Value::Map(ref map) => Box::new(
+                map.iter()
+                    .map(|(key, value)| Some((key.clone(), Variant(value.to_ref_arg()?))))
+                    .collect::<Option<HashMap<_, _>>>()?,
+            ),
+            Value::Unsupported => return None,
+        })
+    }
+}
+
 impl<'a> From<&'a str> for Value {
     fn from(string: &'a str) -> Value {
         Value::String(String::from(string))
@@ -450,7 +680,7 @@ impl<'a> dbus::arg::Get<'a> for Value {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use dbus::arg::{Append, RefArg, Variant};
+    use dbus::arg::Append;
     use dbus::ffidisp::{BusType, Connection, ConnectionItem};
     use dbus::Message;
 
@@ -610,4 +840,146 @@ mod tests {
         assert!(hash.is_map());
         assert_eq!(hash.into_map(), Some(expected));
     }
+
+    #[test]
+    fn it_extracts_integer_arrays() {
+        let array = Value::Array(vec![
+            Value::I16(1),
+            Value::U32(2),
+            Value::String("three".into()),
+            Value::I64(4),
+            Value::U64(5),
+            Value::U64(u64::MAX),
+        ]);
+
+        assert_eq!(array.as_i64_array(), Some(vec![1, 2, 4, 5]));
+        assert_eq!(Value::U64(u64::MAX).as_i64_array(), None);
+        assert_eq!(Value::I32(42).as_i64_array(), Some(vec![42]));
+        assert_eq!(Value::Bool(true).as_i64_array(), None);
+    }
+
+    #[test]
+    fn it_extracts_float_arrays() {
+        let array = Value::Array(vec![
+            Value::F64(0.5),
+            Value::I32(2),
+            Value::Bool(false),
+            Value::U64(3),
+        ]);
+
+        assert_eq!(array.as_f64_array(), Some(vec![0.5, 2.0, 3.0]));
+        assert_eq!(Value::F64(1.5).as_f64_array(), Some(vec![1.5]));
+        assert_eq!(Value::from("1.5").as_f64_array(), None);
+    }
+
+    #[test]
+    fn it_pretty_prints_nested_values() {
+        let mut inner = HashMap::new();
+        inner.insert(String::from("b"), Value::F64(1.0));
+        inner.insert(String::from("a"), Value::Array(vec![]));
+
+        let mut outer = HashMap::new();
+        outer.insert(String::from("nested"), Value::Map(inner));
+        outer.insert(
+            String::from("list"),
+            Value::Array(vec![Value::from("x"), Value::Bool(true)]),
+        );
+
+        assert_eq!(
+            Value::Map(outer).to_pretty_string(2),
+            concat!(
+                "{\n",
+                "  \"list\": [\n",
+                "    \"x\",\n",
+                "    true\n",
+                "  ],\n",
+                "  \"nested\": {\n",
+                "    \"a\": [],\n",
+                "    \"b\": 1.0\n",
+                "  }\n",
+                "}",
+            )
+        );
+        assert_eq!(Value::U8(7).to_pretty_string(4), "7");
+    }
+
+    #[test]
+    fn it_leniently_converts_integers_to_booleans() {
+        assert_eq!(Value::Bool(true).as_bool_lenient(), Some(true));
+        assert_eq!(Value::I32(0).as_bool_lenient(), Some(false));
+        assert_eq!(Value::U8(1).as_bool_lenient(), Some(true));
+        assert_eq!(Value::U64(1).as_bool_lenient(), Some(true));
+        assert_eq!(Value::I64(2).as_bool_lenient(), None);
+        assert_eq!(Value::I16(-1).as_bool_lenient(), None);
+        assert_eq!(Value::from("true").as_bool_lenient(), None);
+
+        assert_eq!(Value::I32(1).as_bool(), None);
+    }
+
+    #[test]
+    fn it_converts_integers_to_durations() {
+        assert_eq!(
+            Value::U64(1_500_000).as_duration(),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(Value::U8(3).as_duration(), Some(Duration::from_micros(3)));
+        assert_eq!(
+            Value::I64(2_000).as_duration(),
+            Some(Duration::from_millis(2))
+        );
+        assert_eq!(Value::I32(0).as_duration(), Some(Duration::from_secs(0)));
+        assert_eq!(Value::I64(-1).as_duration(), None);
+        assert_eq!(Value::F64(1.0).as_duration(), None);
+        assert_eq!(Value::from("1").as_duration(), None);
+    }
+
+    #[test]
+    fn it_walks_nested_values() {
+        let mut inner = HashMap::new();
+        inner.insert(
+            String::from("list"),
+            Value::Array(vec![Value::U8(1), Value::U8(2)]),
+        );
+        let mut outer = HashMap::new();
+        outer.insert(String::from("inner"), Value::Map(inner));
+        let value = Value::Map(outer);
+
+        assert_eq!(value.get_path(&[]), Some(&value));
+        assert_eq!(value.get_path(&["inner", "list", "1"]), Some(&Value::U8(2)));
+        assert_eq!(value.get_path(&["inner", "list", "2"]), None);
+        assert_eq!(value.get_path(&["inner", "list", "first"]), None);
+        assert_eq!(value.get_path(&["inner", "missing"]), None);
+        assert_eq!(value.get_path(&["inner", "list", "0", "deeper"]), None);
+
+        let list = value.get_path(&["inner", "list"]).unwrap();
+        assert_eq!(list.get_index(0), Some(&Value::U8(1)));
+        assert_eq!(list.get_index(5), None);
+        assert_eq!(Value::U8(1).get_index(0), None);
+    }
+
+    #[test]
+    fn it_converts_values_for_sending() {
+        let mut map = HashMap::new();
+        map.insert(String::from("rating"), Value::U8(4));
+        let values = vec![
+            Value::from("hello"),
+            Value::I64(-3),
+            Value::F64(0.5),
+            Value::Bool(true),
+            Value::Array(vec![Value::from("a"), Value::from("b")]),
+            Value::Array(vec![Value::U16(1), Value::from("b")]),
+            Value::Map(map),
+        ];
+
+        for value in values {
+            let message = send_value_over_dbus(Variant(value.to_ref_arg().unwrap()));
+            let received: Value = message.read1().unwrap();
+            assert_eq!(received, value);
+        }
+
+        assert!(Value::Unsupported.to_ref_arg().is_none());
+        assert!(Value::Array(vec![Value::Unsupported])
+            .to_ref_arg()
+            .is_none());
+    }
 }
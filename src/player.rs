@@ -1,12 +1,17 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::ops::Range;
+use std::ops::{Range, RangeInclusive};
 use std::rc::Rc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use dbus::ffidisp::{ConnPath, Connection};
 use dbus::strings::{BusName, Path};
+use thiserror::Error;
 
-use super::{DBusError, LoopStatus, MetadataValue, PlaybackStatus, TrackID, TrackList};
+use super::{
+    DBusError, LoopStatus, MetadataValue, PlaybackStatus, Playlist, PlaylistOrdering, TrackID,
+    TrackList,
+};
 use crate::event::PlayerEvents;
 use crate::extensions::DurationExtensions;
 use crate::generated::OrgMprisMediaPlayer2;
@@ -36,6 +41,8 @@ pub struct Player {
     identity: String,
     timeout_ms: i32,
     has_tracklist_interface: bool,
+    metadata_cache: RefCell<Option<(Instant, Metadata)>>,
+    uri_schemes_cache: RefCell<Option<Vec<String>>>,
 }
 
 impl Player {
@@ -83,6 +90,8 @@ impl Player {
             identity,
             timeout_ms,
             has_tracklist_interface,
+            metadata_cache: RefCell::new(None),
+            uri_schemes_cache: RefCell::new(None),
         })
     }
 
@@ -156,10 +165,54 @@ impl Player {
     }
 
     /// Returns the player's unique D-Bus bus name (usually something like `:1.1337`).
+    ///
+    /// The unique name is determined when the [`Player`] is created. A player that quits and
+    /// starts again gets a new unique name; see
+    /// [`refresh_unique_name`](Self::refresh_unique_name).
     pub fn unique_name(&self) -> &str {
         &self.unique_name
     }
 
+    /// Asks the bus for the current owner of the player's [`bus_name`](Self::bus_name), and
+    /// starts using it as the [`unique_name`](Self::unique_name).
+    ///
+    /// Call this after the player has been restarted, for example when
+    /// [`is_running`](Self::is_running) returned [`false`] but a new process owns the bus name.
+    /// Otherwise signals from the new process are never seen by this [`Player`]. Cached values,
+    /// like the ones used by [`cached_metadata`](Self::cached_metadata), are cleared if the unique
+    /// name changed.
+    ///
+    /// **Note:** Events still queued for the old process are discarded when the unique name
+    /// changes, including its final [`PlayerShutDown`](crate::Event::PlayerShutDown). Read them
+    /// from your [`PlayerEvents`] or [`ProgressTracker`] before calling this if you need them.
+    ///
+    /// Returns [`true`] if the unique name changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no process owns the bus name, which means that the player is not
+    /// running.
+    pub fn refresh_unique_name(&mut self) -> Result<bool, DBusError> {
+        let unique_name = self
+            .connection
+            .determine_unique_name(&self.bus_name)
+            .ok_or_else(|| {
+                DBusError::Miscellaneous(String::from(
+                    "Could not determine player's unique name. Is it running?",
+                ))
+            })?;
+
+        if unique_name == self.unique_name {
+            return Ok(false);
+        }
+
+        // Nothing will read the events of the old process anymore.
+        self.connection.pending_events(&self.unique_name);
+        self.unique_name = unique_name;
+        self.clear_caches();
+        Ok(true)
+    }
+
     /// Returns the player's MPRIS [`Identity`][identity].
     ///
     /// This is usually the application's name, like `Spotify`.
@@ -169,6 +222,19 @@ impl Player {
         &self.identity
     }
 
+    /// Returns a name for the player that is suitable for showing to a user.
+    ///
+    /// This is the player's [`identity`](Self::identity), unless the player reported an empty
+    /// identity. In that case the [`bus_name_trimmed`](Self::bus_name_trimmed) is returned
+    /// instead.
+    pub fn display_name(&self) -> &str {
+        if self.identity.trim().is_empty() {
+            self.bus_name_trimmed()
+        } else {
+            self.identity()
+        }
+    }
+
     /// Checks if the Player implements the `org.mpris.MediaPlayer2.TrackList` interface.
     pub fn supports_track_lists(&self) -> bool {
         self.has_tracklist_interface
@@ -205,6 +271,36 @@ impl Player {
             .map_err(|e| e.into())
     }
 
+    /// Returns the player's `SupportedUriSchemes` property, normalized to lowercase.
+    ///
+    /// The property is only read from the player the first time this is called; later calls
+    /// return the same schemes without talking to the player. Use
+    /// [`get_supported_uri_schemes`](Self::get_supported_uri_schemes) to always read the current
+    /// value.
+    pub fn cached_supported_uri_schemes(&self) -> Result<Vec<String>, DBusError> {
+        if let Some(ref schemes) = *self.uri_schemes_cache.borrow() {
+            return Ok(schemes.clone());
+        }
+
+        let schemes: Vec<String> = self
+            .get_supported_uri_schemes()?
+            .iter()
+            .map(|scheme| scheme.to_ascii_lowercase())
+            .collect();
+        self.uri_schemes_cache.replace(Some(schemes.clone()));
+        Ok(schemes)
+    }
+
+    /// Checks if the given URI scheme (like `file` or `https`) is one of the player's
+    /// [`cached_supported_uri_schemes`](Self::cached_supported_uri_schemes). The comparison
+    /// ignores case.
+    pub fn supports_uri_scheme(&self, scheme: &str) -> Result<bool, DBusError> {
+        Ok(self
+            .cached_supported_uri_schemes()?
+            .iter()
+            .any(|supported| supported.eq_ignore_ascii_case(scheme)))
+    }
+
     /// Returns the player's `HasTrackList` property.
     ///
     /// See: [MPRIS2 specification about `HasTrackList`][track_list].
@@ -226,12 +322,35 @@ impl Player {
     ///
     /// Return [`Some`] containing the current value of the position. If the setting is not
     /// supported, return [`None`]
+    ///
+    /// The property is read directly, so this also works for players that fail `GetAll`. Some
+    /// players list the property but fail when it is read, as they only support the `Seeked`
+    /// signal. This is also treated as the setting not being supported.
     pub fn checked_get_position(&self) -> Result<Option<Duration>, DBusError> {
-        if self.has_position()? {
-            Ok(Some(self.get_position()?))
-        } else {
-            Ok(None)
-        }
+        handle_optional_property(self.connection_path().position())
+            .map(|position| position.map(|p| Duration::from_micros_ext(p as u64)))
+    }
+
+    /// Checks if the player reports a `Position` that actually moves, by reading it twice a few
+    /// hundred milliseconds apart.
+    ///
+    /// Some players, like Spotify, always report the same position and only send `Seeked`
+    /// signals. Use this to detect them instead of relying on the player's identity.
+    ///
+    /// **Note:** This is only meaningful while the player is
+    /// [`Playing`](PlaybackStatus::Playing); the position of a paused or stopped player never
+    /// moves, so [`false`] is returned. This method blocks while waiting between the readings.
+    pub fn probe_position_support(&self) -> Result<bool, DBusError> {
+        let first = match self.checked_get_position()? {
+            Some(position) => position,
+            None => return Ok(false),
+        };
+
+        std::thread::sleep(Duration::from_millis(300));
+
+        Ok(self
+            .checked_get_position()?
+            .map_or(false, |second| second != first))
     }
 
     /// Returns the player's MPRIS `position` as a count of microseconds since the start of the
@@ -248,8 +367,10 @@ impl Player {
     /// Current [`TrackID`] must be provided to avoid race conditions with the player, in case it
     /// changes tracks while the signal is being sent.
     ///
-    /// **Note:** There is currently no good way to retrieve the current [`TrackID`] through the
-    /// `mpris` library. You will have to manually retrieve it through D-Bus until implemented.
+    /// The current [`TrackID`] can be read from [`Metadata::track_id`]. If the [`TrackID`] does
+    /// not match the current track, the player ignores the call and no error is returned. See
+    /// [`set_position_checked`](Self::set_position_checked) for a version that checks the
+    /// [`TrackID`] first.
     ///
     /// See: [MPRIS2 specification about `SetPosition`][set_position].
     ///
@@ -280,13 +401,36 @@ impl Player {
         }
     }
 
+    /// Sets the position of the current track to the given position (as a [`Duration`]), if
+    /// `track_id` is the ID of the current track.
+    ///
+    /// Players ignore `SetPosition` calls for other tracks than the current one, so this checks
+    /// the [`TrackID`] from the player's [`Metadata`] first. Returns a boolean to show if the
+    /// signal was sent or not.
+    ///
+    /// Unlike [`checked_set_position`](Self::checked_set_position), this does not check if the
+    /// player supports the "Position" setting.
+    pub fn set_position_checked(
+        &self,
+        track_id: &TrackID,
+        position: Duration,
+    ) -> Result<bool, DBusError> {
+        if self.get_metadata()?.track_id().as_ref() != Some(track_id) {
+            return Ok(false);
+        }
+
+        self.set_position(track_id.clone(), &position).map(|_| true)
+    }
+
     /// Sets the position of the current track to the given position (in microseconds).
     ///
     /// Current [`TrackID`] must be provided to avoid race conditions with the player, in case it
     /// changes tracks while the signal is being sent.
     ///
-    /// **Note:** There is currently no good way to retrieve the current [`TrackID`] through the
-    /// `mpris` library. You will have to manually retrieve it through D-Bus until implemented.
+    /// The current [`TrackID`] can be read from [`Metadata::track_id`]. If the [`TrackID`] does
+    /// not match the current track, the player ignores the call and no error is returned. See
+    /// [`set_position_checked`](Self::set_position_checked) for a version that checks the
+    /// [`TrackID`] first.
     ///
     /// See: [MPRIS2 specification about `SetPosition`][set_position].
     ///
@@ -382,10 +526,137 @@ impl Player {
             .and_then(|min| self.get_maximum_playback_rate().map(|max| min..max))
     }
 
+    /// Gets the allowed values for playback rate, both bounds inclusive, or [`None`] if the player
+    /// does not report them.
+    pub(crate) fn checked_get_playback_rate_bounds(
+        &self,
+    ) -> Result<Option<RangeInclusive<f64>>, DBusError> {
+        let connection_path = self.connection_path();
+        let min = handle_optional_property(connection_path.minimum_rate())?;
+        let max = handle_optional_property(connection_path.maximum_rate())?;
+        Ok(min.and_then(|min| max.map(|max| min..=max)))
+    }
+
     /// Query the player for current metadata.
     ///
     /// See [`Metadata`] for more information about what is included here.
     pub fn get_metadata(&self) -> Result<Metadata, DBusError> {
+        self.get_metadata_map()
+            .map(Metadata::from)
+            .map_err(DBusError::from)
+    }
+
+    /// Query the player for current metadata, reusing the result of a previous call to this
+    /// method if it is not older than `max_age`.
+    ///
+    /// This is useful when reading several parts of the metadata in a short time, like when
+    /// rendering a single frame of a UI, without having to pass the [`Metadata`] around. The
+    /// cache is only used by this method; [`get_metadata`](Self::get_metadata) always queries the
+    /// player.
+    pub fn cached_metadata(&self, max_age: Duration) -> Result<Metadata, DBusError> {
+        if let Some((fetched_at, ref metadata)) = *self.metadata_cache.borrow() {
+            if fetched_at.elapsed() <= max_age {
+                return Ok(metadata.clone());
+            }
+        }
+
+        let metadata = self.get_metadata()?;
+        self.metadata_cache
+            .replace(Some((Instant::now(), metadata.clone())));
+        Ok(metadata)
+    }
+
+    /// Query the player for current metadata, if the player implements the
+    /// `org.mpris.MediaPlayer2.Player` interface.
+    ///
+    /// Returns [`None`] if the interface or its `Metadata` property is missing, which the MPRIS2
+    /// specification allows. Other errors will be returned as [`Err`].
+    ///
+    /// See [`get_metadata`](Self::get_metadata).
+    pub fn checked_get_metadata(&self) -> Result<Option<Metadata>, DBusError> {
+        handle_optional_property(self.get_metadata_map()).map(|o| o.map(Metadata::from))
+    }
+
+    /// Query the player for the most commonly needed state in a single D-Bus call.
+    ///
+    /// This fetches all properties of the `org.mpris.MediaPlayer2.Player` interface at once, which
+    /// is cheaper than calling [`get_playback_status`](Self::get_playback_status),
+    /// [`get_metadata`](Self::get_metadata) and [`get_position`](Self::get_position) separately.
+    ///
+    /// Some players fail the whole call when one property, usually `Position`, cannot be read.
+    /// Those players are asked for each value separately instead.
+    ///
+    /// See [`NowPlaying`] for the returned values.
+    pub fn now_playing(&self) -> Result<NowPlaying, DBusError> {
+        match self.get_all_player_properties() {
+            Ok(properties) => NowPlaying::from_properties(properties),
+            Err(_) => Ok(NowPlaying::new(
+                self.get_playback_status()?,
+                self.checked_get_metadata()?.unwrap_or_default(),
+                self.checked_get_position()?,
+            )),
+        }
+    }
+
+    pub(crate) fn get_all_player_properties(
+        &self,
+    ) -> Result<HashMap<String, MetadataValue>, DBusError> {
+        let (properties,): (HashMap<String, MetadataValue>,) = self.connection_path().method_call(
+            "org.freedesktop.DBus.Properties",
+            "GetAll",
+            ("org.mpris.MediaPlayer2.Player",),
+        )?;
+
+        Ok(properties)
+    }
+
+    /// Reads any property of the player, including non-standard ones.
+    ///
+    /// This is an escape hatch for vendor-specific properties that this library does not know
+    /// about; prefer the typed methods for the standard ones. The `interface` is not checked, so
+    /// this can read properties of any interface that the player exports on its MPRIS object.
+    pub fn get_property_raw(
+        &self,
+        interface: &str,
+        name: &str,
+    ) -> Result<MetadataValue, DBusError> {
+        let (value,): (MetadataValue,) = self.connection_path().method_call(
+            "org.freedesktop.DBus.Properties",
+            "Get",
+            (interface, name),
+        )?;
+
+        Ok(value)
+    }
+
+    /// Writes any property of the player, including non-standard ones.
+    ///
+    /// See [`get_property_raw`](Self::get_property_raw). Arrays that only contain strings are sent
+    /// as arrays of strings, and other arrays as arrays of variants.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `value` is, or contains, a `MetadataValue::Unsupported`.
+    pub fn set_property_raw(
+        &self,
+        interface: &str,
+        name: &str,
+        value: &MetadataValue,
+    ) -> Result<(), DBusError> {
+        let value = value.to_ref_arg().ok_or_else(|| {
+            DBusError::Miscellaneous(String::from("Cannot send unsupported metadata values"))
+        })?;
+
+        self.connection_path()
+            .method_call(
+                "org.freedesktop.DBus.Properties",
+                "Set",
+                (interface, name, dbus::arg::Variant(value)),
+            )
+            .map_err(DBusError::from)
+    }
+
+    fn get_metadata_map(&self) -> Result<HashMap<String, MetadataValue>, dbus::Error> {
         use dbus::ffidisp::stdintf::org_freedesktop_dbus::Properties;
 
         let connection_path = self.connection_path();
@@ -395,8 +666,6 @@ impl Player {
             "org.mpris.MediaPlayer2.Player",
             "Metadata",
         )
-        .map(Metadata::from)
-        .map_err(DBusError::from)
     }
 
     /// Query the player for the current tracklist.
@@ -419,6 +688,29 @@ impl Player {
         .map_err(DBusError::from)
     }
 
+    /// Query the player for a window of the current tracklist, returning at most `count`
+    /// [`TrackID`]s starting at index `start`.
+    ///
+    /// MPRIS does not support paging, so the full list of IDs is still transferred. Use this
+    /// together with [`get_tracks_metadata`](Self::get_tracks_metadata) to only load metadata for
+    /// the tracks that you need.
+    ///
+    /// An empty [`Vec`] is returned if `start` is past the end of the list.
+    pub fn get_track_list_range(
+        &self,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<TrackID>, DBusError> {
+        Ok(self
+            .get_track_list()?
+            .ids()
+            .iter()
+            .skip(start)
+            .take(count)
+            .cloned()
+            .collect())
+    }
+
     /// Query the player for the current tracklist.
     ///
     /// **Note:** It's more expensive to rebuild this each time rather than trying to keep the same
@@ -434,6 +726,33 @@ impl Player {
         }
     }
 
+    /// Predict which track will become current after a call to [`next`](Self::next), using the
+    /// current track and the order of the player's [`TrackList`].
+    ///
+    /// When the player loops the playlist, the first track is predicted after the last one.
+    ///
+    /// Returns [`None`] if the player does not support track lists, if the current track is not
+    /// on the list, or if the current track is the last one and the playlist does not loop.
+    ///
+    /// **Note:** This is a best-effort prediction. When shuffle is enabled, players are free to
+    /// pick any track next, and the prediction will likely be wrong for players that do not
+    /// reorder their track list to match the shuffled order.
+    pub fn peek_next_track(&self) -> Result<Option<TrackID>, DBusError> {
+        let track_list = match self.checked_get_track_list()? {
+            Some(track_list) => track_list,
+            None => return Ok(None),
+        };
+
+        let current_id = match self.get_metadata()?.track_id() {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        let loops_playlist = self.checked_get_loop_status()? == Some(LoopStatus::Playlist);
+
+        Ok(track_list.id_after(&current_id, loops_playlist).cloned())
+    }
+
     /// Query the player to see if it allows changes to its TrackList.
     ///
     /// Will return [`Err`] if Player isn't supporting the [`TrackList`] interface.
@@ -563,10 +882,15 @@ impl Player {
     ///
     /// If the player that you are controlling / querying has shut down, then this would return
     /// false. You can use this to do graceful restarts, begin looking for another player, etc.
+    ///
+    /// This also returns [`false`] if the player has quit and was started again. Method calls are
+    /// sent to the bus name and reach the new process, but signals are only matched against the
+    /// unique name of the old one, so events from the new process are missed. Use
+    /// [`refresh_unique_name`](Self::refresh_unique_name) to follow the new process.
     pub fn is_running(&self) -> bool {
         self.connection()
-            .name_has_owner(self.bus_name.to_string())
-            .unwrap_or(false)
+            .determine_unique_name(self.bus_name.as_str())
+            .map_or(false, |owner| owner == self.unique_name)
     }
 
     pub(crate) fn connection(&self) -> &PooledConnection {
@@ -582,6 +906,37 @@ impl Player {
         self.connection_path().play_pause().map_err(|e| e.into())
     }
 
+    /// Send a `PlayPause` signal to the player and wait for its playback status to change.
+    ///
+    /// Returns the new [`PlaybackStatus`]. If the status did not change within `timeout`, the
+    /// current (unchanged) status is returned instead. This could happen if the player cannot
+    /// pause the current media, for example.
+    ///
+    /// This processes events on the D-Bus connection while waiting, so it does not rely on
+    /// sleeping for a fixed amount of time before reading the status.
+    ///
+    /// See: [MPRIS2 specification about `PlayPause`][play_pause]
+    ///
+    /// [play_pause]: https://specifications.freedesktop.org/mpris-spec/latest/Player_Interface.html#Method:PlayPause
+    pub fn play_pause_and_status(&self, timeout: Duration) -> Result<PlaybackStatus, DBusError> {
+        let old_status = self.get_playback_status()?;
+        self.play_pause()?;
+
+        let new_status = self.wait_until(timeout, || {
+            let status = self.get_playback_status()?;
+            Ok(if status != old_status {
+                Some(status)
+            } else {
+                None
+            })
+        })?;
+
+        match new_status {
+            Some(status) => Ok(status),
+            None => self.get_playback_status(),
+        }
+    }
+
     /// Send a `Play` signal to the player.
     ///
     /// See: [MPRIS2 specification about `Play`][play].
@@ -609,6 +964,25 @@ impl Player {
         self.connection_path().stop().map_err(|e| e.into())
     }
 
+    /// Send a `Stop` signal to the player and wait for its playback status to become
+    /// [`Stopped`](PlaybackStatus::Stopped).
+    ///
+    /// Returns [`true`] if the player reported being stopped within `timeout`, or [`false`]
+    /// otherwise.
+    ///
+    /// See: [`stop`](Self::stop) and [`play_pause_and_status`](Self::play_pause_and_status).
+    pub fn stop_and_wait(&self, timeout: Duration) -> Result<bool, DBusError> {
+        self.stop()?;
+
+        self.wait_until(timeout, || {
+            Ok(match self.get_playback_status()? {
+                PlaybackStatus::Stopped => Some(()),
+                _ => None,
+            })
+        })
+        .map(|stopped| stopped.is_some())
+    }
+
     /// Send a `Next` signal to the player.
     ///
     /// See: [MPRIS2 specification about `Next`][next].
@@ -627,8 +1001,30 @@ impl Player {
         self.connection_path().previous().map_err(|e| e.into())
     }
 
+    /// Send an `OpenUri` signal to the player.
+    ///
+    /// > Opens the Uri given as an argument
+    /// >
+    /// > If the playback is stopped, starts playing
+    /// >
+    /// > If the uri scheme or the mime-type of the uri to open is not supported, this method does
+    /// > nothing and may raise an error.
+    ///
+    /// See: [MPRIS2 specification about `OpenUri`][open_uri] and the
+    /// [`checked_open_uri`](Self::checked_open_uri) method.
+    ///
+    /// [open_uri]: https://specifications.freedesktop.org/mpris-spec/latest/Player_Interface.html#Method:OpenUri
+    pub fn open_uri(&self, uri: &str) -> Result<(), DBusError> {
+        self.connection_path().open_uri(uri).map_err(|e| e.into())
+    }
+
     /// Send a `Seek` signal to the player.
     ///
+    /// The offset is given in microseconds, which is the native resolution of MPRIS. Positive
+    /// values seek forwards and negative values seek backwards. See
+    /// [`seek_forwards`](Self::seek_forwards) and [`seek_backwards`](Self::seek_backwards) for
+    /// versions taking a [`Duration`].
+    ///
     /// See: [MPRIS2 specification about `Seek`][seek].
     ///
     /// [seek]: https://specifications.freedesktop.org/mpris-spec/latest/Player_Interface.html#Method:Seek
@@ -640,9 +1036,12 @@ impl Player {
 
     /// Tell the player to seek forwards.
     ///
+    /// Offsets too large to be represented in microseconds are clamped to the largest possible
+    /// offset.
+    ///
     /// See: [`seek`](Self::seek) method.
     pub fn seek_forwards(&self, offset: &Duration) -> Result<(), DBusError> {
-        self.seek(DurationExtensions::as_micros(offset) as i64)
+        self.seek(DurationExtensions::as_micros_i64(offset))
     }
 
     /// Send a `Raise` signal to the player.
@@ -705,9 +1104,12 @@ impl Player {
 
     /// Tell the player to seek backwards.
     ///
+    /// Offsets too large to be represented in microseconds are clamped to the largest possible
+    /// offset.
+    ///
     /// See: [`seek`](Self::seek) method.
     pub fn seek_backwards(&self, offset: &Duration) -> Result<(), DBusError> {
-        self.seek(-(DurationExtensions::as_micros(offset) as i64))
+        self.seek(-DurationExtensions::as_micros_i64(offset))
     }
 
     /// Go to a specific track on the [`Player`]'s [`TrackList`].
@@ -749,6 +1151,94 @@ impl Player {
             .map_err(DBusError::from)
     }
 
+    /// Add a URI to the TrackList, like [`add_track`](Self::add_track), and wait for the player to
+    /// announce the [`TrackID`] of the new track.
+    ///
+    /// The player announces new tracks with a `TrackAdded` signal. The first such signal after the
+    /// call, for a track that either has the same URL or no URL in its metadata, is assumed to be
+    /// for the new track. Returns [`None`] if no such signal is received within `timeout`.
+    ///
+    /// Requires the player to implement the `TrackList` interface.
+    ///
+    /// See: [MPRIS2 specification about `TrackAdded`][track_added].
+    ///
+    /// [track_added]: https://specifications.freedesktop.org/mpris-spec/latest/Track_List_Interface.html#Signal:TrackAdded
+    pub fn add_track_and_get_id(
+        &self,
+        uri: &str,
+        after: &TrackID,
+        set_as_current: bool,
+        timeout: Duration,
+    ) -> Result<Option<TrackID>, DBusError> {
+        let already_queued = self
+            .connection
+            .peek_pending_events(&self.unique_name, |events| events.len());
+
+        self.add_track(uri, after, set_as_current)?;
+
+        self.wait_until(timeout, || {
+            Ok(self
+                .connection
+                .peek_pending_events(&self.unique_name, |events| {
+                    events
+                        .iter()
+                        .skip(already_queued)
+                        .find_map(|event| match event {
+                            MprisEvent::TrackAdded { metadata, .. }
+                                if metadata.url().map_or(true, |url| url == uri) =>
+                            {
+                                metadata.track_id()
+                            }
+                            _ => None,
+                        })
+                }))
+        })
+    }
+
+    /// Add several URIs to the TrackList, in order, after the specified [`TrackID`]. The first URI
+    /// is optionally set as current.
+    ///
+    /// MPRIS has no method for adding several tracks at once, so this calls `AddTrack` once per
+    /// URI, using [`add_track_and_get_id`](Self::add_track_and_get_id) to add each URI after the
+    /// track that was added before it. `timeout` is how long to wait for the player to announce
+    /// each new [`TrackID`].
+    ///
+    /// Use [`TrackID::no_track`] as `after` to add the tracks to the start of the list.
+    ///
+    /// Requires the player to implement the `TrackList` interface.
+    ///
+    /// # Errors
+    ///
+    /// Stops at the first URI that could not be added, or whose [`TrackID`] the player did not
+    /// announce within `timeout`, as the next URI could not be placed after it. The returned
+    /// [`AddTracksError`] contains its index in `uris`; all URIs before it have been added, and
+    /// the URI itself might have been added too if only its ID was missing.
+    pub fn add_tracks(
+        &self,
+        uris: &[&str],
+        after: &TrackID,
+        set_first_current: bool,
+        timeout: Duration,
+    ) -> Result<(), AddTracksError> {
+        let mut previous = after.clone();
+
+        for (index, uri) in uris.iter().enumerate() {
+            let set_as_current = set_first_current && index == 0;
+            previous = self
+                .add_track_and_get_id(uri, &previous, set_as_current, timeout)
+                .and_then(|new_id| {
+                    new_id.ok_or_else(|| {
+                        DBusError::Miscellaneous(String::from(
+                            "Player did not announce the ID of the added track in time",
+                        ))
+                    })
+                })
+                .map_err(|error| AddTracksError { index, error })?;
+        }
+
+        Ok(())
+    }
+
     /// Add a URI to the start of the TrackList and optionally set it as current.
     ///
     /// Requires the player to implement the `TrackList` interface.
@@ -916,6 +1406,29 @@ impl Player {
         }
     }
 
+    /// Sends an `OpenUri` signal to the player, if the scheme of the URI is in the player's
+    /// [`SupportedUriSchemes`](Self::get_supported_uri_schemes).
+    ///
+    /// Returns a boolean to show if the signal was sent or not. URIs without a scheme are never
+    /// sent. The supported schemes are cached; see
+    /// [`cached_supported_uri_schemes`](Self::cached_supported_uri_schemes).
+    ///
+    /// See: [MPRIS2 specification about `OpenUri`][open_uri].
+    ///
+    /// [open_uri]: https://specifications.freedesktop.org/mpris-spec/latest/Player_Interface.html#Method:OpenUri
+    pub fn checked_open_uri(&self, uri: &str) -> Result<bool, DBusError> {
+        let scheme = match uri_scheme(uri) {
+            Some(scheme) => scheme,
+            None => return Ok(false),
+        };
+
+        if self.supports_uri_scheme(scheme)? {
+            self.open_uri(uri).map(|_| true)
+        } else {
+            Ok(false)
+        }
+    }
+
     /// Queries the player to see if it can be raised or not.
     ///
     /// See: [MPRIS2 specification about `CanRaise`][can_raise] and the [`raise`](Self::raise) method.
@@ -958,6 +1471,25 @@ impl Player {
         self.connection_path().can_control().map_err(|e| e.into())
     }
 
+    /// Queries the player to see if it can go to the previous and next tracks, in a single D-Bus
+    /// call.
+    ///
+    /// Returns a tuple of `(can_go_previous, can_go_next)`. Capabilities that the player does not
+    /// report are treated as [`false`].
+    ///
+    /// See: [`can_go_previous`](Self::can_go_previous) and [`can_go_next`](Self::can_go_next).
+    pub fn navigation_caps(&self) -> Result<(bool, bool), DBusError> {
+        let properties = self.get_all_player_properties()?;
+        let capability = |name: &str| {
+            properties
+                .get(name)
+                .and_then(MetadataValue::as_bool)
+                .unwrap_or(false)
+        };
+
+        Ok((capability("CanGoPrevious"), capability("CanGoNext")))
+    }
+
     /// Queries the player to see if it can go to next or not.
     ///
     /// See: [MPRIS2 specification about `CanGoNext`][can_next].
@@ -1163,6 +1695,18 @@ impl Player {
         }
     }
 
+    /// Toggle the "Shuffle" setting of the player, returning the new state.
+    ///
+    /// This reads the current setting and then writes the opposite value, without any locking. If
+    /// something else changes the setting in between, one of the changes could be lost.
+    ///
+    /// See: [`get_shuffle`](Self::get_shuffle) and [`set_shuffle`](Self::set_shuffle).
+    pub fn toggle_shuffle(&self) -> Result<bool, DBusError> {
+        let new_state = !self.get_shuffle()?;
+        self.set_shuffle(new_state)?;
+        Ok(new_state)
+    }
+
     /// Query the player for the current loop status.
     ///
     /// See: [MPRIS2 specification about  `LoopStatus`][loop_status].
@@ -1228,6 +1772,19 @@ impl Player {
         self.connection_path().volume().map_err(DBusError::from)
     }
 
+    /// Get the volume of the player, clamped to be between 0.0 and 1.0.
+    ///
+    /// Some players report volumes above 1.0, or even negative volumes. Use this method when you
+    /// need a value that can be shown directly, like on a volume bar. Use
+    /// [`get_volume`](Self::get_volume) to get the value as reported by the player.
+    ///
+    /// See: [MPRIS2 specification about `Volume`][vol].
+    ///
+    /// [vol]: https://specifications.freedesktop.org/mpris-spec/latest/Player_Interface.html#Property:Volume
+    pub fn get_volume_clamped(&self) -> Result<f64, DBusError> {
+        self.get_volume().map(clamp_volume)
+    }
+
     /// Gets the "Volume" setting, if the player indicates that it supports it.
     ///
     /// Returns [`Some`] containing the current value of the position. If the setting is not
@@ -1289,6 +1846,109 @@ impl Player {
         }
     }
 
+    /// Query the player for at most `max_count` of its playlists, starting at `index`, sorted by
+    /// `order`.
+    ///
+    /// Requires the player to implement the `Playlists` interface. Use
+    /// [`checked_get_playlist_count`](Self::checked_get_playlist_count) to find out if it does.
+    ///
+    /// See: [MPRIS2 specification about `GetPlaylists`][get_playlists]
+    ///
+    /// [get_playlists]: https://specifications.freedesktop.org/mpris-spec/latest/Playlists_Interface.html#Method:GetPlaylists
+    pub fn get_playlists(
+        &self,
+        index: u32,
+        max_count: u32,
+        order: PlaylistOrdering,
+        reverse_order: bool,
+    ) -> Result<Vec<Playlist>, DBusError> {
+        use crate::generated::OrgMprisMediaPlayer2Playlists;
+
+        self.connection_path()
+            .get_playlists(index, max_count, &order.dbus_value(), reverse_order)
+            .map(|playlists| playlists.into_iter().map(Playlist::from).collect())
+            .map_err(DBusError::from)
+    }
+
+    /// Start playing the given [`Playlist`].
+    ///
+    /// Requires the player to implement the `Playlists` interface.
+    ///
+    /// See: [MPRIS2 specification about `ActivatePlaylist`][activate_playlist]
+    ///
+    /// [activate_playlist]: https://specifications.freedesktop.org/mpris-spec/latest/Playlists_Interface.html#Method:ActivatePlaylist
+    pub fn activate_playlist(&self, playlist: &Playlist) -> Result<(), DBusError> {
+        use crate::generated::OrgMprisMediaPlayer2Playlists;
+
+        self.connection_path()
+            .activate_playlist(playlist.as_path()?)
+            .map_err(DBusError::from)
+    }
+
+    /// Query the player for the currently active [`Playlist`], if any.
+    ///
+    /// Requires the player to implement the `Playlists` interface.
+    ///
+    /// See: [MPRIS2 specification about `ActivePlaylist`][active_playlist]
+    ///
+    /// [active_playlist]: https://specifications.freedesktop.org/mpris-spec/latest/Playlists_Interface.html#Property:ActivePlaylist
+    pub fn get_active_playlist(&self) -> Result<Option<Playlist>, DBusError> {
+        use crate::generated::OrgMprisMediaPlayer2Playlists;
+
+        let (valid, playlist) = self.connection_path().active_playlist()?;
+        Ok(if valid {
+            Some(Playlist::from(playlist))
+        } else {
+            None
+        })
+    }
+
+    /// Query the player for the orderings it supports in
+    /// [`get_playlists`](Self::get_playlists).
+    ///
+    /// Orderings that are not part of the MPRIS specification are skipped.
+    ///
+    /// See: [MPRIS2 specification about `Orderings`][orderings]
+    ///
+    /// [orderings]: https://specifications.freedesktop.org/mpris-spec/latest/Playlists_Interface.html#Property:Orderings
+    pub fn get_playlist_orderings(&self) -> Result<Vec<PlaylistOrdering>, DBusError> {
+        use crate::generated::OrgMprisMediaPlayer2Playlists;
+
+        Ok(self
+            .connection_path()
+            .orderings()?
+            .iter()
+            .filter_map(|value| PlaylistOrdering::from_dbus_value(value))
+            .collect())
+    }
+
+    /// Query the player for the number of playlists it has.
+    ///
+    /// Requires the player to implement the `Playlists` interface. See
+    /// [`checked_get_playlist_count`](Self::checked_get_playlist_count) for players that might
+    /// not.
+    ///
+    /// See: [MPRIS2 specification about `PlaylistCount`][playlist_count]
+    ///
+    /// [playlist_count]: https://specifications.freedesktop.org/mpris-spec/latest/Playlists_Interface.html#Property:PlaylistCount
+    pub fn get_playlist_count(&self) -> Result<u32, DBusError> {
+        use crate::generated::OrgMprisMediaPlayer2Playlists;
+
+        self.connection_path()
+            .playlist_count()
+            .map_err(DBusError::from)
+    }
+
+    /// Query the player for the number of playlists it has, returning [`None`] if the player does
+    /// not implement the `Playlists` interface.
+    ///
+    /// See [`get_playlist_count`](Self::get_playlist_count).
+    pub fn checked_get_playlist_count(&self) -> Result<Option<u32>, DBusError> {
+        use crate::generated::OrgMprisMediaPlayer2Playlists;
+
+        handle_optional_property(self.connection_path().playlist_count())
+    }
+
     fn connection_path(&self) -> ConnPath<'_, &Connection> {
         self.connection.with_path(
             self.bus_name.as_str().into(),
@@ -1308,20 +1968,195 @@ impl Player {
         }
     }
 
+    /// Blocks until player gets an event on the bus, or until `duration` runs out.
+    ///
+    /// Other player events will also be recorded, but will not cause this function to return.
+    pub(crate) fn process_events_blocking_until_received_for(&self, duration: Duration) {
+        let start = Instant::now();
+
+        while !self.connection.has_pending_events(&self.unique_name) {
+            match duration.checked_sub(start.elapsed()) {
+                Some(time_left) if time_left > Duration::from_millis(0) => self
+                    .connection
+                    .process_events_blocking_until_received_for(time_left),
+                _ => return,
+            }
+        }
+    }
+
+    /// Calls `check` every time an event is received on the connection, until it returns
+    /// [`Some`] or the `timeout` runs out. `check` is also called once before waiting for events.
+    ///
+    /// Events are not consumed from the player's queue, so any [`ProgressTracker`] or
+    /// [`PlayerEvents`] for this player will still see them.
+    fn wait_until<T, F>(&self, timeout: Duration, mut check: F) -> Result<Option<T>, DBusError>
+    where
+        F: FnMut() -> Result<Option<T>, DBusError>,
+    {
+        let start = Instant::now();
+
+        loop {
+            if let Some(value) = check()? {
+                return Ok(Some(value));
+            }
+
+            match timeout.checked_sub(start.elapsed()) {
+                Some(time_left) if time_left > Duration::from_millis(0) => self
+                    .connection
+                    .process_events_blocking_until_received_for(time_left),
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    /// Forget all cached values, so they are read from the player again when next used.
+    pub(crate) fn clear_caches(&self) {
+        self.metadata_cache.replace(None);
+        self.uri_schemes_cache.replace(None);
+    }
+
     /// Return any events that are pending (for this player) on the connection.
     pub(crate) fn pending_events(&self) -> Vec<MprisEvent> {
         self.connection.pending_events(&self.unique_name)
     }
 }
 
+/// Error returned by [`Player::add_tracks`] when one of the URIs could not be added.
+#[derive(Debug, Error)]
+#[error("Could not add track {index}: {error}")]
+pub struct AddTracksError {
+    index: usize,
+    error: DBusError,
+}
+
+impl AddTracksError {
+    /// The index in the list of URIs of the URI that could not be added. All URIs before it were
+    /// added.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The error that caused the URI to not be added.
+    pub fn error(&self) -> &DBusError {
+        &self.error
+    }
+}
+
+impl From<AddTracksError> for DBusError {
+    fn from(error: AddTracksError) -> Self {
+        error.error
+    }
+}
+
+/// A summary of what a [`Player`] is currently playing.
+///
+/// Returned by [`Player::now_playing`].
+#[derive(Debug, Clone)]
+pub struct NowPlaying {
+    status: PlaybackStatus,
+    title: Option<String>,
+    artists: Option<Vec<String>>,
+    position: Option<Duration>,
+    length: Option<Duration>,
+}
+
+impl NowPlaying {
+    fn from_properties(mut properties: HashMap<String, MetadataValue>) -> Result<Self, DBusError> {
+        let status = properties
+            .get("PlaybackStatus")
+            .and_then(MetadataValue::as_str)
+            .ok_or_else(|| {
+                DBusError::Miscellaneous(String::from("Player did not report a PlaybackStatus"))
+            })?
+            .parse()?;
+
+        let metadata = properties
+            .remove("Metadata")
+            .and_then(MetadataValue::into_map)
+            .map(Metadata::from)
+            .unwrap_or_default();
+
+        let position = properties
+            .get("Position")
+            .and_then(MetadataValue::as_i64)
+            .filter(|&position| position >= 0)
+            .map(|position| Duration::from_micros_ext(position as u64));
+
+        Ok(NowPlaying::new(status, metadata, position))
+    }
+
+    fn new(status: PlaybackStatus, metadata: Metadata, position: Option<Duration>) -> Self {
+        NowPlaying {
+            status,
+            title: metadata.title().map(String::from),
+            artists: metadata
+                .artists()
+                .map(|artists| artists.into_iter().map(String::from).collect()),
+            position,
+            length: metadata.length(),
+        }
+    }
+
+    /// The playback status of the player.
+    pub fn status(&self) -> PlaybackStatus {
+        self.status
+    }
+
+    /// The title of the current track, if any.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// The artists of the current track, if any.
+    pub fn artists(&self) -> Option<&[String]> {
+        self.artists.as_deref()
+    }
+
+    /// The position in the current track, if the player reported it.
+    pub fn position(&self) -> Option<Duration> {
+        self.position
+    }
+
+    /// The length of the current track, if the player reported it.
+    pub fn length(&self) -> Option<Duration> {
+        self.length
+    }
+}
+
+/// Returns the scheme part of a URI, as defined by RFC 3986.
+fn uri_scheme(uri: &str) -> Option<&str> {
+    let scheme = &uri[..uri.find(':')?];
+    let mut chars = scheme.chars();
+    let valid = chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+
+    if valid {
+        Some(scheme)
+    } else {
+        None
+    }
+}
+
+fn clamp_volume(volume: f64) -> f64 {
+    if volume.is_nan() {
+        0.0
+    } else {
+        volume.clamp(0.0, 1.0)
+    }
+}
+
 fn handle_optional_property<T>(result: Result<T, dbus::Error>) -> Result<Option<T>, DBusError> {
     if let Err(ref error) = result {
-        if let Some(error_name) = error.name() {
-            if error_name == "org.freedesktop.DBus.Error.InvalidArgs" {
+        match error.name() {
+            Some("org.freedesktop.DBus.Error.InvalidArgs")
+            | Some("org.freedesktop.DBus.Error.NotSupported")
+            | Some("org.freedesktop.DBus.Error.UnknownInterface")
+            | Some("org.freedesktop.DBus.Error.UnknownProperty") => {
                 // This property was likely just missing, which means that the player has not
                 // implemented it.
                 return Ok(None);
             }
+            _ => {}
         }
     }
 
@@ -1340,3 +2175,100 @@ fn has_tracklist_interface(connection: ConnPath<'_, &Connection>) -> Result<bool
     let xml: String = connection.introspect()?;
     Ok(xml.contains("org.mpris.MediaPlayer2.TrackList"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_player::{TestPlayer, TestPlayerState};
+
+    #[test]
+    fn it_clamps_volumes() {
+        assert_eq!(clamp_volume(0.5), 0.5);
+        assert_eq!(clamp_volume(1.5), 1.0);
+        assert_eq!(clamp_volume(-0.2), 0.0);
+        assert_eq!(clamp_volume(f64::NAN), 0.0);
+    }
+
+    #[test]
+    fn it_builds_now_playing_from_properties() {
+        let mut metadata = HashMap::new();
+        metadata.insert(String::from("xesam:title"), MetadataValue::from("Song"));
+        metadata.insert(
+            String::from("xesam:artist"),
+            MetadataValue::Array(vec![MetadataValue::from("Artist")]),
+        );
+        metadata.insert(String::from("mpris:length"), MetadataValue::I64(3_000_000));
+
+        let mut properties = HashMap::new();
+        properties.insert(
+            String::from("PlaybackStatus"),
+            MetadataValue::from("Paused"),
+        );
+        properties.insert(String::from("Metadata"), MetadataValue::Map(metadata));
+        properties.insert(String::from("Position"), MetadataValue::I64(1_500_000));
+
+        let now_playing = NowPlaying::from_properties(properties).unwrap();
+        assert_eq!(now_playing.status(), PlaybackStatus::Paused);
+        assert_eq!(now_playing.title(), Some("Song"));
+        assert_eq!(now_playing.artists(), Some(&[String::from("Artist")][..]));
+        assert_eq!(now_playing.position(), Some(Duration::from_millis(1500)));
+        assert_eq!(now_playing.length(), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn it_requires_playback_status_for_now_playing() {
+        let mut properties = HashMap::new();
+        properties.insert(String::from("Position"), MetadataValue::I64(0));
+
+        assert!(NowPlaying::from_properties(properties).is_err());
+    }
+
+    #[test]
+    fn it_treats_an_unreadable_position_as_unsupported_when_get_all_fails() {
+        let test_player = TestPlayer::new(TestPlayerState {
+            playback_status: "Paused",
+            fail_get_all: true,
+            fail_position: true,
+            ..TestPlayerState::default()
+        });
+        let player = test_player.player();
+
+        assert_eq!(player.checked_get_position().unwrap(), None);
+
+        let now_playing = player.now_playing().unwrap();
+        assert_eq!(now_playing.status(), PlaybackStatus::Paused);
+        assert_eq!(now_playing.title(), Some("Test track"));
+        assert_eq!(now_playing.position(), None);
+    }
+
+    #[test]
+    fn it_stops_adding_tracks_when_an_id_is_not_announced() {
+        let test_player = TestPlayer::new(TestPlayerState {
+            tracks: Some(Vec::new()),
+            ..TestPlayerState::default()
+        });
+        let player = test_player.player();
+
+        let error = player
+            .add_tracks(
+                &["file:///first.ogg", "file:///second.ogg"],
+                &TrackID::no_track(),
+                false,
+                Duration::from_millis(50),
+            )
+            .unwrap_err();
+
+        assert_eq!(error.index(), 0);
+        assert!(matches!(error.error(), DBusError::Miscellaneous(_)));
+    }
+
+    #[test]
+    fn it_finds_uri_schemes() {
+        assert_eq!(uri_scheme("file:///home/user/song.mp3"), Some("file"));
+        assert_eq!(uri_scheme("spotify:track:1234"), Some("spotify"));
+        assert_eq!(uri_scheme("svn+ssh://example.com"), Some("svn+ssh"));
+        assert_eq!(uri_scheme("/home/user/song.mp3"), None);
+        assert_eq!(uri_scheme(":nothing"), None);
+        assert_eq!(uri_scheme("1http://example.com"), None);
+    }
+}
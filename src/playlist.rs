@@ -0,0 +1,145 @@
+use std::fmt;
+
+use super::DBusError;
+
+/// A playlist, as returned by the [`MediaPlayer2.Playlists`][playlists] interface.
+///
+/// See [`Player::get_playlists`](crate::Player::get_playlists).
+///
+/// [playlists]: https://specifications.freedesktop.org/mpris-spec/latest/Playlists_Interface.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Playlist {
+    id: String,
+    name: String,
+    icon: String,
+}
+
+/// The orderings that [`Player::get_playlists`](crate::Player::get_playlists) can sort playlists
+/// by.
+///
+/// Players are not required to support all of them.
+///
+/// See: [MPRIS2 specification about `Playlist_Ordering`][ordering].
+///
+/// [ordering]: https://specifications.freedesktop.org/mpris-spec/latest/Playlists_Interface.html#Enum:Playlist_Ordering
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum PlaylistOrdering {
+    /// Alphabetical ordering by name, ascending.
+    Alphabetical,
+
+    /// Ordering by creation date, oldest first.
+    CreationDate,
+
+    /// Ordering by last modified date, oldest first.
+    ModifiedDate,
+
+    /// Ordering by date of last playback, oldest first.
+    LastPlayDate,
+
+    /// A user-defined ordering.
+    UserDefined,
+}
+
+impl Playlist {
+    /// The D-Bus object path identifying the playlist.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The name of the playlist, suitable for showing to a user.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The URI of an icon or image for the playlist, if the player provided one.
+    pub fn icon(&self) -> Option<&str> {
+        if self.icon.is_empty() {
+            None
+        } else {
+            Some(&self.icon)
+        }
+    }
+
+    pub(crate) fn as_path(&self) -> Result<dbus::Path<'_>, DBusError> {
+        dbus::Path::new(self.id.as_str()).map_err(DBusError::Miscellaneous)
+    }
+}
+
+impl<'a> From<(dbus::Path<'a>, String, String)> for Playlist {
+    fn from((id, name, icon): (dbus::Path<'a>, String, String)) -> Playlist {
+        Playlist {
+            id: id.to_string(),
+            name,
+            icon,
+        }
+    }
+}
+
+impl PlaylistOrdering {
+    pub(crate) fn dbus_value(self) -> String {
+        self.to_string()
+    }
+
+    pub(crate) fn from_dbus_value(value: &str) -> Option<Self> {
+        match value {
+            "Alphabetical" => Some(PlaylistOrdering::Alphabetical),
+            "Created" => Some(PlaylistOrdering::CreationDate),
+            "Modified" => Some(PlaylistOrdering::ModifiedDate),
+            "Played" => Some(PlaylistOrdering::LastPlayDate),
+            "User" => Some(PlaylistOrdering::UserDefined),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the ordering as the exact string used by MPRIS, like `Created`.
+impl fmt::Display for PlaylistOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PlaylistOrdering::Alphabetical => "Alphabetical",
+            PlaylistOrdering::CreationDate => "Created",
+            PlaylistOrdering::ModifiedDate => "Modified",
+            PlaylistOrdering::LastPlayDate => "Played",
+            PlaylistOrdering::UserDefined => "User",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_treats_empty_icons_as_missing() {
+        let playlist = Playlist::from((
+            dbus::Path::new("/playlists/1").unwrap(),
+            String::from("Favorites"),
+            String::new(),
+        ));
+
+        assert_eq!(playlist.id(), "/playlists/1");
+        assert_eq!(playlist.name(), "Favorites");
+        assert_eq!(playlist.icon(), None);
+    }
+
+    #[test]
+    fn it_formats_orderings_as_mpris_values() {
+        assert_eq!(PlaylistOrdering::Alphabetical.dbus_value(), "Alphabetical");
+        assert_eq!(PlaylistOrdering::LastPlayDate.dbus_value(), "Played");
+        assert_eq!(PlaylistOrdering::UserDefined.dbus_value(), "User");
+
+        for ordering in &[
+            PlaylistOrdering::Alphabetical,
+            PlaylistOrdering::CreationDate,
+            PlaylistOrdering::ModifiedDate,
+            PlaylistOrdering::LastPlayDate,
+            PlaylistOrdering::UserDefined,
+        ] {
+            assert_eq!(
+                PlaylistOrdering::from_dbus_value(&ordering.dbus_value()),
+                Some(*ordering)
+            );
+        }
+        assert_eq!(PlaylistOrdering::from_dbus_value("Shuffled"), None);
+    }
+}
@@ -1,7 +1,8 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
+use dbus::arg::{RefArg, Variant};
 use dbus::ffidisp::{ConnPath, Connection};
 use dbus::strings::{BusName, Path};
 use dbus::Message;
@@ -15,10 +16,10 @@ use crate::track_list::TrackID;
 pub(crate) struct PooledConnection {
     connection: Connection,
     events: RefCell<HashMap<String, Vec<MprisEvent>>>,
+    name_owner_timeout_ms: Cell<i32>,
 }
 
-const GET_NAME_OWNER_TIMEOUT: i32 = 100; // ms
-const NAME_HAS_OWNER_TIMEOUT: i32 = 100; // ms
+const DEFAULT_NAME_OWNER_TIMEOUT_MS: i32 = 100; // ms
 
 impl PooledConnection {
     pub(crate) fn new(connection: Connection) -> Self {
@@ -38,6 +39,7 @@ impl PooledConnection {
         PooledConnection {
             connection,
             events: RefCell::new(HashMap::new()),
+            name_owner_timeout_ms: Cell::new(DEFAULT_NAME_OWNER_TIMEOUT_MS),
         }
     }
 
@@ -54,34 +56,27 @@ impl PooledConnection {
         &self.connection
     }
 
-    pub(crate) fn determine_unique_name<S: Into<String>>(&self, bus_name: S) -> Option<String> {
-        let get_name_owner = Message::new_method_call(
-            "org.freedesktop.DBus",
-            "/",
-            "org.freedesktop.DBus",
-            "GetNameOwner",
-        )
-        .unwrap()
-        .append1(bus_name.into());
+    /// The timeout (in milliseconds) used when asking the bus who owns a name.
+    pub(crate) fn name_owner_timeout_ms(&self) -> i32 {
+        self.name_owner_timeout_ms.get()
+    }
 
-        self.connection
-            .send_with_reply_and_block(get_name_owner, GET_NAME_OWNER_TIMEOUT)
-            .ok()
-            .and_then(|reply| reply.get1())
+    pub(crate) fn set_name_owner_timeout_ms(&self, timeout_ms: i32) {
+        self.name_owner_timeout_ms.set(timeout_ms);
     }
 
-    pub(crate) fn name_has_owner<S: Into<String>>(&self, bus_name: S) -> Option<bool> {
-        let name_has_owner = Message::new_method_call(
+    pub(crate) fn determine_unique_name<S: Into<String>>(&self, bus_name: S) -> Option<String> {
+        let get_name_owner = Message::new_method_call(
             "org.freedesktop.DBus",
             "/",
             "org.freedesktop.DBus",
-            "NameHasOwner",
+            "GetNameOwner",
         )
         .unwrap()
         .append1(bus_name.into());
 
         self.connection
-            .send_with_reply_and_block(name_has_owner, NAME_HAS_OWNER_TIMEOUT)
+            .send_with_reply_and_block(get_name_owner, self.name_owner_timeout_ms())
             .ok()
             .and_then(|reply| reply.get1())
     }
@@ -97,6 +92,17 @@ impl PooledConnection {
             .unwrap_or(false)
     }
 
+    /// Calls `f` with the pending events of a bus without removing them from the queue.
+    pub(crate) fn peek_pending_events<T, F>(&self, bus_name: &str, f: F) -> T
+    where
+        F: FnOnce(&[MprisEvent]) -> T,
+    {
+        match self.events.try_borrow() {
+            Ok(events) => f(events.get(bus_name).map_or(&[], Vec::as_slice)),
+            Err(_) => f(&[]),
+        }
+    }
+
     /// Removes all pending events from a bus' queue and returns them.
     ///
     /// If you want to non-destructively check if a bus has anything queued, use
@@ -113,6 +119,36 @@ impl PooledConnection {
     pub(crate) fn process_events_blocking_for(&self, duration: Duration) {
         let start = Instant::now();
 
+        while start.elapsed() < duration {
+            let ms_left = duration
+                .checked_sub(start.elapsed())
+                .map(|d| DurationExtensions::as_millis(&d))
+                .unwrap_or(0);
+
+            // Don't bother if we have very little time left
+            if ms_left < 2 {
+                break;
+            }
+
+            // Only take a single message at a time; every message restarts the timeout of the
+            // iterator, so a stream of unrelated messages could otherwise block far past the
+            // deadline.
+            if let Some(message) = self
+                .connection
+                .incoming(ms_left as u32)
+                .next()
+                .and_then(MprisMessage::try_parse)
+            {
+                self.process_message(message);
+            }
+        }
+    }
+
+    /// Process events in a blocking fashion until any new event is found, or until the
+    /// deadline/timebox [`Duration`] runs out.
+    pub(crate) fn process_events_blocking_until_received_for(&self, duration: Duration) {
+        let start = Instant::now();
+
         while start.elapsed() < duration {
             let ms_left = duration
                 .checked_sub(start.elapsed())
@@ -127,10 +163,11 @@ impl PooledConnection {
             if let Some(message) = self
                 .connection
                 .incoming(ms_left as u32)
-                .flat_map(MprisMessage::try_parse)
                 .next()
+                .and_then(MprisMessage::try_parse)
             {
                 self.process_message(message);
+                return;
             }
         }
     }
@@ -176,11 +213,14 @@ impl PooledConnection {
                     events.insert(old_owner, vec![MprisEvent::PlayerQuit]);
                 }
             }
-            MprisMessage::PlayerPropertiesChanged { unique_name } => {
+            MprisMessage::PlayerPropertiesChanged {
+                unique_name,
+                changed,
+            } => {
                 events
                     .entry(unique_name)
                     .or_default()
-                    .push(MprisEvent::PlayerPropertiesChanged);
+                    .push(MprisEvent::PlayerPropertiesChanged { changed });
             }
             MprisMessage::Seeked {
                 unique_name,
@@ -255,7 +295,10 @@ impl From<Connection> for PooledConnection {
 #[derive(Debug)]
 pub(crate) enum MprisEvent {
     PlayerQuit,
-    PlayerPropertiesChanged,
+    PlayerPropertiesChanged {
+        /// Names of the changed and invalidated properties.
+        changed: Vec<String>,
+    },
     Seeked {
         position_in_us: u64,
     },
@@ -285,6 +328,7 @@ pub(crate) enum MprisMessage {
     },
     PlayerPropertiesChanged {
         unique_name: String,
+        changed: Vec<String>,
     },
     Seeked {
         unique_name: String,
@@ -374,7 +418,17 @@ fn try_parse_properties_changed(message: &Message) -> Option<MprisMessage> {
     let interface_name: String = iter.read().ok()?;
     match interface_name.as_ref() {
         "org.mpris.MediaPlayer2.Player" => {
-            Some(MprisMessage::PlayerPropertiesChanged { unique_name })
+            let changed_properties: HashMap<String, Variant<Box<dyn RefArg>>> =
+                iter.read().unwrap_or_default();
+            let invalidated_properties: Vec<String> = iter.read().unwrap_or_default();
+
+            let mut changed: Vec<String> = changed_properties.into_keys().collect();
+            changed.extend(invalidated_properties);
+
+            Some(MprisMessage::PlayerPropertiesChanged {
+                unique_name,
+                changed,
+            })
         }
         "org.mpris.MediaPlayer2.TrackList" => {
             Some(MprisMessage::TrackListPropertiesChanged { unique_name })
@@ -443,3 +497,38 @@ fn try_parse_track_metadata_changed(message: &Message) -> Option<MprisMessage> {
         metadata,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_changed_and_invalidated_player_properties() {
+        let mut changed: HashMap<String, Variant<Box<dyn RefArg>>> = HashMap::new();
+        changed.insert("Rate".into(), Variant(Box::new(2.0f64)));
+
+        let mut message = Message::signal(
+            &Path::from(MPRIS2_PATH),
+            &"org.freedesktop.DBus.Properties".into(),
+            &"PropertiesChanged".into(),
+        )
+        .append3(
+            "org.mpris.MediaPlayer2.Player",
+            changed,
+            vec![String::from("MaximumRate")],
+        );
+        message.set_sender(Some(":1.42".into()));
+
+        match MprisMessage::try_parse(message) {
+            Some(MprisMessage::PlayerPropertiesChanged {
+                unique_name,
+                mut changed,
+            }) => {
+                changed.sort();
+                assert_eq!(unique_name, ":1.42");
+                assert_eq!(changed, vec!["MaximumRate", "Rate"]);
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+    }
+}
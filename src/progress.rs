@@ -1,7 +1,8 @@
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use thiserror::Error;
 
-use super::{DBusError, LoopStatus, PlaybackStatus, TrackList, TrackListError};
+use super::{DBusError, LoopStatus, MetadataValue, PlaybackStatus, TrackList, TrackListError};
 use crate::extensions::DurationExtensions;
 use crate::metadata::Metadata;
 use crate::player::Player;
@@ -14,7 +15,7 @@ use crate::pooled_connection::MprisEvent;
 ///
 /// It is up to you to decide on how outdated information you want to rely on when implementing
 /// progress rendering.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Progress {
     metadata: Metadata,
     playback_status: PlaybackStatus,
@@ -25,6 +26,7 @@ pub struct Progress {
     instant: Instant,
 
     position: Duration,
+    position_supported: bool,
     rate: f64,
     current_volume: f64,
 }
@@ -41,6 +43,18 @@ pub struct ProgressTracker<'a> {
     last_progress: Progress,
 }
 
+/// Controller for tracking the [`Progress`] of several [`Player`]s at the same time.
+///
+/// All players must share the same D-Bus connection, which is the case for [`Player`]s found by
+/// the same [`PlayerFinder`](crate::PlayerFinder). Call the [`tick_all`](Self::tick_all) method to
+/// get the most current [`Progress`] data for all of them.
+#[derive(Debug)]
+pub struct MultiProgressTracker<'a> {
+    trackers: Vec<ProgressTracker<'a>>,
+    interval: Duration,
+    last_tick: Instant,
+}
+
 /// Return value of [`ProgressTracker::tick`](ProgressTracker::tick), which gives details about the latest refresh.
 #[derive(Debug)]
 pub struct ProgressTick<'a> {
@@ -211,16 +225,7 @@ impl<'a> ProgressTracker<'a> {
     /// }
     /// ```
     pub fn tick(&mut self) -> ProgressTick<'_> {
-        let mut player_quit = false;
-        let mut progress_changed = false;
-        let mut track_list_changed = false;
-        let old_shuffle = self.last_progress.shuffle;
-
-        // Calculate time left until we're expected to return with new data.
-        let time_left = self
-            .interval
-            .checked_sub(self.last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_millis(0));
+        let time_left = time_until_next_tick(self.last_tick, self.interval, Instant::now());
 
         // Refresh events if we're not late.
         if time_left > Duration::from_millis(0) {
@@ -229,18 +234,72 @@ impl<'a> ProgressTracker<'a> {
                 .process_events_blocking_for(time_left);
         }
 
-        // Process events that are queued up for us
+        // Start the next interval before processing the events, so the time spent talking to the
+        // player is counted against it instead of delaying every tick.
+        self.last_tick = Instant::now();
+        self.process_pending_events()
+    }
+
+    /// Returns [`true`] if the position of the last [`Progress`] is moving forward, which is only
+    /// the case when the player is [`Playing`](PlaybackStatus::Playing) at a rate above zero.
+    ///
+    /// When this returns [`false`], [`Progress::position`] stays the same until an event changes
+    /// the state of the player, so there is no need to redraw a progress bar on every tick.
+    pub fn is_position_advancing(&self) -> bool {
+        self.last_progress.playback_status.is_playing() && self.last_progress.rate > 0.0
+    }
+
+    /// Mutable access to the [`TrackList`] of the tracker, for example to enable
+    /// [`TrackList::set_operations_limit`]. Returns [`None`] if the player has no track list.
+    pub fn track_list_mut(&mut self) -> Option<&mut TrackList> {
+        self.track_list.as_mut()
+    }
+
+    /// Like [`tick`](Self::tick), but if the position is not
+    /// [advancing](Self::is_position_advancing) it blocks until the player sends an event instead
+    /// of returning at the next interval.
+    ///
+    /// This avoids waking up for nothing while a player is paused or stopped. Don't use this if
+    /// you need to do other work between ticks, as there is no upper limit on how long it blocks.
+    pub fn tick_until_change(&mut self) -> ProgressTick<'_> {
+        if self.is_position_advancing() {
+            return self.tick();
+        }
+
+        self.player.process_events_blocking_until_received();
+        self.last_tick = Instant::now();
+        self.process_pending_events()
+    }
+
+    /// Process the events that are already queued up for the player, without blocking for new
+    /// ones.
+    fn process_pending_events(&mut self) -> ProgressTick<'_> {
+        let mut player_quit = false;
+        let mut progress_changed = false;
+        let mut refreshed = false;
+        let mut track_list_changed = false;
+        let old_shuffle = self.last_progress.shuffle;
+
         for event in self.player.pending_events().into_iter() {
             match event {
                 MprisEvent::PlayerQuit => {
                     player_quit = true;
                     break;
                 }
-                MprisEvent::PlayerPropertiesChanged | MprisEvent::Seeked { .. } => {
-                    if !progress_changed {
+                MprisEvent::PlayerPropertiesChanged { .. } => {
+                    // A single refresh picks up all property changes in this batch.
+                    if !refreshed {
                         progress_changed |= self.refresh_player();
+                        refreshed = true;
                     }
                 }
+                MprisEvent::Seeked { position_in_us } => {
+                    // The signal carries the exact new position, so there is no need to ask the
+                    // player for it.
+                    self.last_progress
+                        .seeked_to(Duration::from_micros_ext(position_in_us));
+                    progress_changed = true;
+                }
                 MprisEvent::TrackListPropertiesChanged => {
                     track_list_changed |= self.refresh_track_list();
                 }
@@ -278,7 +337,6 @@ impl<'a> ProgressTracker<'a> {
             track_list_changed |= self.refresh_track_list();
         }
 
-        self.last_tick = Instant::now();
         ProgressTick {
             progress: &self.last_progress,
             track_list: self.track_list.as_ref(),
@@ -293,10 +351,16 @@ impl<'a> ProgressTracker<'a> {
     /// This will ignore the interval and perform a refresh anyway. The new [`Progress`] will be
     /// saved, and the [`TrackList`] will be refreshed.
     ///
+    /// Use this to reset the tracker to the player's current state in case you suspect that it
+    /// has drifted, for example after missing signals. Nothing that the tracker has derived from
+    /// previous events is kept, and the values cached by the [`Player`], like the ones returned by
+    /// [`Player::cached_supported_uri_schemes`], are read again when next used.
+    ///
     /// # Errors
     ///
     /// Returns an error if the refresh failed.
     pub fn force_refresh(&mut self) -> Result<(), ProgressError> {
+        self.player.clear_caches();
         self.last_progress = Progress::from_player(self.player)?;
         if let Some(ref mut list) = self.track_list {
             list.reload(self.player)?;
@@ -305,11 +369,11 @@ impl<'a> ProgressTracker<'a> {
     }
 
     fn refresh_player(&mut self) -> bool {
-        if let Ok(progress) = Progress::from_player(self.player) {
-            self.last_progress = progress;
-            return true;
-        }
-        false
+        let player = self.player;
+        self.last_progress.refresh_from(
+            || Progress::from_player(player),
+            || player.get_playback_status(),
+        )
     }
 
     fn refresh_track_list(&mut self) -> bool {
@@ -320,8 +384,171 @@ impl<'a> ProgressTracker<'a> {
     }
 }
 
+impl<'a> MultiProgressTracker<'a> {
+    /// Construct a new [`MultiProgressTracker`] for the provided [`Player`]s.
+    ///
+    /// The `interval_ms` value is the desired time between ticks when calling the
+    /// [`tick_all`](Self::tick_all) method.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in case Player metadata or state retrieval over DBus fails, or if the
+    /// players do not share the same D-Bus connection.
+    pub fn new<I>(players: I, interval_ms: u32) -> Result<Self, DBusError>
+    where
+        I: IntoIterator<Item = &'a Player>,
+    {
+        let trackers = players
+            .into_iter()
+            .map(|player| ProgressTracker::new(player, interval_ms))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(first) = trackers.first() {
+            let connection = first.player.connection();
+            if trackers
+                .iter()
+                .any(|tracker| !std::ptr::eq(tracker.player.connection(), connection))
+            {
+                return Err(DBusError::Miscellaneous(String::from(
+                    "All players in a MultiProgressTracker must share the same D-Bus connection",
+                )));
+            }
+        }
+
+        Ok(MultiProgressTracker {
+            trackers,
+            interval: Duration::from_millis(u64::from(interval_ms)),
+            last_tick: Instant::now(),
+        })
+    }
+
+    /// Returns a [`ProgressTick`] for each player at each interval, or as close to each interval
+    /// as possible. The ticks are keyed by the players' bus names.
+    ///
+    /// This works like [`ProgressTracker::tick`], but the time until the next interval is spent
+    /// processing D-Bus events for all of the players at once, instead of blocking once per
+    /// player.
+    ///
+    /// If there are no players being tracked, an empty map is returned right away.
+    pub fn tick_all(&mut self) -> HashMap<&'a str, ProgressTick<'_>> {
+        let time_left = time_until_next_tick(self.last_tick, self.interval, Instant::now());
+
+        if time_left > Duration::from_millis(0) {
+            if let Some(tracker) = self.trackers.first() {
+                tracker
+                    .player
+                    .connection()
+                    .process_events_blocking_for(time_left);
+            }
+        }
+
+        self.last_tick = Instant::now();
+        self.trackers
+            .iter_mut()
+            .map(|tracker| {
+                let player: &'a Player = tracker.player;
+                (player.bus_name(), tracker.process_pending_events())
+            })
+            .collect()
+    }
+}
+
+/// Time left until the tick following the one at `last_tick` is due, or zero if it is already late.
+fn time_until_next_tick(last_tick: Instant, interval: Duration, now: Instant) -> Duration {
+    (last_tick + interval).saturating_duration_since(now)
+}
+
 impl Progress {
     pub(crate) fn from_player(player: &Player) -> Result<Progress, DBusError> {
+        // Read everything in a single round trip, so all values come from the same point in time.
+        // Players that fail to answer that are asked for each property separately instead.
+        let progress = Progress::from_batched_reads(
+            || player.get_all_player_properties(),
+            |name| player.get_property_raw("org.mpris.MediaPlayer2.Player", name),
+        )?;
+
+        match progress {
+            Some(progress) => Ok(progress),
+            None => Progress::from_player_properties_one_by_one(player),
+        }
+    }
+
+    /// Reads all properties with `get_all`, then uses `get` to read only the required properties
+    /// that were missing from the result.
+    ///
+    /// Returns [`None`] if `get_all` fails, or if the required properties are invalid.
+    fn from_batched_reads<A, G>(get_all: A, mut get: G) -> Result<Option<Progress>, DBusError>
+    where
+        A: FnOnce() -> Result<HashMap<String, MetadataValue>, DBusError>,
+        G: FnMut(&str) -> Result<MetadataValue, DBusError>,
+    {
+        let mut properties = match get_all() {
+            Ok(properties) => properties,
+            Err(_) => return Ok(None),
+        };
+        let instant = Instant::now();
+
+        for &name in &["PlaybackStatus", "Metadata"] {
+            if !properties.contains_key(name) {
+                properties.insert(String::from(name), get(name)?);
+            }
+        }
+
+        Ok(Progress::from_properties(properties, instant))
+    }
+
+    /// Builds a [`Progress`] from the result of `GetAll` on the `Player` interface.
+    ///
+    /// Optional properties that are missing are not supported by the player, and get the same
+    /// defaults as when they are read one by one. Returns [`None`] if `Metadata` or
+    /// `PlaybackStatus` are missing or invalid.
+    fn from_properties(
+        mut properties: HashMap<String, MetadataValue>,
+        instant: Instant,
+    ) -> Option<Progress> {
+        let playback_status = properties
+            .get("PlaybackStatus")
+            .and_then(MetadataValue::as_str)
+            .and_then(|status| status.parse().ok())?;
+        let metadata = properties
+            .remove("Metadata")
+            .and_then(MetadataValue::into_map)
+            .map(Metadata::from)?;
+        let position = properties
+            .get("Position")
+            .and_then(MetadataValue::as_i64)
+            .filter(|&position| position >= 0)
+            .map(|position| Duration::from_micros_ext(position as u64));
+
+        Some(Progress {
+            metadata,
+            playback_status,
+            shuffle: properties
+                .get("Shuffle")
+                .and_then(MetadataValue::as_bool)
+                .unwrap_or(false),
+            loop_status: properties
+                .get("LoopStatus")
+                .and_then(MetadataValue::as_str)
+                .and_then(|status| status.parse().ok())
+                .unwrap_or(LoopStatus::None),
+            rate: properties
+                .get("Rate")
+                .and_then(MetadataValue::as_f64)
+                .unwrap_or(1.0),
+            position: position.unwrap_or_else(|| Duration::new(0, 0)),
+            position_supported: position.is_some(),
+            current_volume: properties
+                .get("Volume")
+                .and_then(MetadataValue::as_f64)
+                .unwrap_or(1.0),
+            instant,
+        })
+    }
+
+    fn from_player_properties_one_by_one(player: &Player) -> Result<Progress, DBusError> {
+        let position = player.checked_get_position()?;
+
         Ok(Progress {
             metadata: player.get_metadata()?,
             playback_status: player.get_playback_status()?,
@@ -330,9 +557,8 @@ impl Progress {
                 .checked_get_loop_status()?
                 .unwrap_or(LoopStatus::None),
             rate: player.checked_get_playback_rate()?.unwrap_or(1.0),
-            position: player
-                .checked_get_position()?
-                .unwrap_or_else(|| Duration::new(0, 0)),
+            position: position.unwrap_or_else(|| Duration::new(0, 0)),
+            position_supported: position.is_some(),
             current_volume: player.checked_get_volume()?.unwrap_or(1.0),
             instant: Instant::now(),
         })
@@ -382,7 +608,56 @@ impl Progress {
     /// [`PlaybackStatus::Playing`] and if both are `0`, then it is likely that this client does not
     /// support positions.
     pub fn position(&self) -> Duration {
-        self.position + self.elapsed()
+        self.position_at(Instant::now())
+    }
+
+    /// Calculates what the position will be (or was) at the given [`Instant`], assuming that the
+    /// playback status and rate stays the same.
+    ///
+    /// This is useful when you want to interpolate the position between ticks, for example when
+    /// rendering at a fixed frame rate. Instants from before this [`Progress`] was created result
+    /// in the [`initial_position`](Self::initial_position).
+    ///
+    /// See [`position`](Self::position) for caveats.
+    pub fn position_at(&self, instant: Instant) -> Duration {
+        self.position + self.elapsed_at(instant)
+    }
+
+    /// Returns how much of the current track is left to play, based on the current
+    /// [`position`](Self::position).
+    ///
+    /// Returns [`None`] if the track has no known [`length`](Self::length), and zero if the
+    /// position is past the end of the track.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.length().map(|length| {
+            length
+                .checked_sub(self.position())
+                .unwrap_or_else(|| Duration::from_millis(0))
+        })
+    }
+
+    /// Returns how much of the current track has been played as a number between `0.0` and
+    /// `1.0`, based on the current [`position`](Self::position). Useful for drawing progress
+    /// bars.
+    ///
+    /// Returns [`None`] if the track has no known [`length`](Self::length), or if the length is
+    /// zero.
+    pub fn fraction_elapsed(&self) -> Option<f64> {
+        let length = self.length()?;
+        if length == Duration::from_millis(0) {
+            return None;
+        }
+
+        let fraction = self.position().as_secs_f64() / length.as_secs_f64();
+        Some(fraction.clamp(0.0, 1.0))
+    }
+
+    /// Returns [`false`] if the player did not report a position when this [`Progress`] was
+    /// created, either because it does not have the property or because reading it failed.
+    ///
+    /// The position is reported as `0` in that case.
+    pub fn supports_position(&self) -> bool {
+        self.position_supported
     }
 
     /// Returns the position that the current track was at when the [`Progress`] was created.
@@ -415,11 +690,49 @@ impl Progress {
         self.current_volume
     }
 
-    fn elapsed(&self) -> Duration {
-        let elapsed_ms = match self.playback_status {
-            PlaybackStatus::Playing => {
-                DurationExtensions::as_millis(&self.age()) as f64 * self.rate
+    /// Moves the position to the one reported by a `Seeked` signal, as of right now.
+    pub(crate) fn seeked_to(&mut self, position: Duration) {
+        self.position = position;
+        self.instant = Instant::now();
+    }
+
+    /// Replaces this with the [`Progress`] returned by `refresh`. Returns [`true`] if anything was
+    /// updated.
+    ///
+    /// If the refresh fails, only the playback status is read with `get_status` instead. Otherwise
+    /// a player that was paused or stopped would keep advancing until the next full refresh.
+    fn refresh_from<R, S>(&mut self, refresh: R, get_status: S) -> bool
+    where
+        R: FnOnce() -> Result<Progress, DBusError>,
+        S: FnOnce() -> Result<PlaybackStatus, DBusError>,
+    {
+        if let Ok(progress) = refresh() {
+            *self = progress;
+            return true;
+        }
+
+        match get_status() {
+            Ok(status) if status != self.playback_status => {
+                self.playback_status_changed(status);
+                true
             }
+            _ => false,
+        }
+    }
+
+    /// Changes the playback status as of right now, keeping the position that has been reached so
+    /// far.
+    pub(crate) fn playback_status_changed(&mut self, status: PlaybackStatus) {
+        let now = Instant::now();
+        self.position = self.position_at(now);
+        self.instant = now;
+        self.playback_status = status;
+    }
+
+    fn elapsed_at(&self, instant: Instant) -> Duration {
+        let age = instant.saturating_duration_since(self.instant);
+        let elapsed_ms = match self.playback_status {
+            PlaybackStatus::Playing => DurationExtensions::as_millis(&age) as f64 * self.rate,
             _ => 0.0,
         };
         Duration::from_millis(elapsed_ms as u64)
@@ -429,6 +742,257 @@ impl Progress {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_player::{TestPlayer, TestPlayerState};
+    use crate::{TrackID, TrackListOperation};
+
+    fn paused_progress(position: Duration, length: Option<Duration>) -> Progress {
+        let mut values = HashMap::new();
+        if let Some(length) = length {
+            values.insert(
+                String::from("mpris:length"),
+                MetadataValue::U64(length.as_micros_i64() as u64),
+            );
+        }
+
+        Progress {
+            metadata: Metadata::from(values),
+            playback_status: PlaybackStatus::Paused,
+            shuffle: false,
+            loop_status: LoopStatus::None,
+            rate: 1.0,
+            position,
+            position_supported: true,
+            current_volume: 0.0,
+            instant: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn it_builds_progress_from_all_properties() {
+        let mut metadata = HashMap::new();
+        metadata.insert(String::from("xesam:title"), MetadataValue::from("Song"));
+
+        let mut properties = HashMap::new();
+        properties.insert(
+            String::from("PlaybackStatus"),
+            MetadataValue::from("Paused"),
+        );
+        properties.insert(String::from("Metadata"), MetadataValue::Map(metadata));
+        properties.insert(String::from("Shuffle"), MetadataValue::Bool(true));
+        properties.insert(String::from("LoopStatus"), MetadataValue::from("Track"));
+        properties.insert(String::from("Rate"), MetadataValue::F64(1.5));
+        properties.insert(String::from("Position"), MetadataValue::I64(2_000_000));
+        properties.insert(String::from("Volume"), MetadataValue::F64(0.5));
+
+        let progress = Progress::from_properties(properties.clone(), Instant::now()).unwrap();
+        assert_eq!(progress.playback_status(), PlaybackStatus::Paused);
+        assert_eq!(progress.metadata().title(), Some("Song"));
+        assert!(progress.shuffle());
+        assert_eq!(progress.loop_status(), LoopStatus::Track);
+        assert_eq!(progress.playback_rate(), 1.5);
+        assert_eq!(progress.initial_position(), Duration::from_secs(2));
+        assert!(progress.supports_position());
+        assert_eq!(progress.current_volume(), 0.5);
+
+        properties.remove("Position");
+        properties.remove("Shuffle");
+        let progress = Progress::from_properties(properties.clone(), Instant::now()).unwrap();
+        assert!(!progress.supports_position());
+        assert!(!progress.shuffle());
+
+        properties.remove("PlaybackStatus");
+        assert!(Progress::from_properties(properties, Instant::now()).is_none());
+    }
+
+    #[test]
+    fn it_reads_all_properties_in_one_call() {
+        let mut properties = HashMap::new();
+        properties.insert(
+            String::from("PlaybackStatus"),
+            MetadataValue::from("Playing"),
+        );
+        properties.insert(String::from("Metadata"), MetadataValue::Map(HashMap::new()));
+
+        let mut get_all_calls = 0;
+        let mut read_properties: Vec<String> = Vec::new();
+        let progress = Progress::from_batched_reads(
+            || {
+                get_all_calls += 1;
+                Ok(properties.clone())
+            },
+            |name| {
+                read_properties.push(String::from(name));
+                Ok(MetadataValue::from("unused"))
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(progress.playback_status(), PlaybackStatus::Playing);
+        assert_eq!(get_all_calls, 1);
+        assert!(read_properties.is_empty());
+    }
+
+    #[test]
+    fn it_only_reads_missing_properties_separately() {
+        let mut properties = HashMap::new();
+        properties.insert(
+            String::from("PlaybackStatus"),
+            MetadataValue::from("Paused"),
+        );
+        properties.insert(String::from("Volume"), MetadataValue::F64(0.5));
+
+        let mut read_properties: Vec<String> = Vec::new();
+        let progress = Progress::from_batched_reads(
+            || Ok(properties),
+            |name| {
+                read_properties.push(String::from(name));
+                Ok(MetadataValue::Map(HashMap::new()))
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(progress.playback_status(), PlaybackStatus::Paused);
+        assert_eq!(progress.current_volume(), 0.5);
+        assert_eq!(read_properties, vec!["Metadata"]);
+
+        let progress = Progress::from_batched_reads(
+            || Err(DBusError::Miscellaneous(String::from("GetAll failed"))),
+            |_| panic!("Should not read properties one by one"),
+        )
+        .unwrap();
+        assert!(progress.is_none());
+    }
+
+    #[test]
+    fn it_calculates_remaining_time_and_fraction() {
+        let progress = paused_progress(Duration::from_secs(30), Some(Duration::from_secs(120)));
+        assert_eq!(progress.remaining(), Some(Duration::from_secs(90)));
+        assert_eq!(progress.fraction_elapsed(), Some(0.25));
+
+        let past_end = paused_progress(Duration::from_secs(130), Some(Duration::from_secs(120)));
+        assert_eq!(past_end.remaining(), Some(Duration::from_secs(0)));
+        assert_eq!(past_end.fraction_elapsed(), Some(1.0));
+
+        let zero_length = paused_progress(Duration::from_secs(0), Some(Duration::from_secs(0)));
+        assert_eq!(zero_length.remaining(), Some(Duration::from_secs(0)));
+        assert_eq!(zero_length.fraction_elapsed(), None);
+
+        let no_length = paused_progress(Duration::from_secs(30), None);
+        assert_eq!(no_length.remaining(), None);
+        assert_eq!(no_length.fraction_elapsed(), None);
+    }
+
+    #[test]
+    fn it_keeps_ticks_close_to_the_interval() {
+        let interval = Duration::from_millis(100);
+        let start = Instant::now();
+        let mut now = start;
+        let mut last_tick = start;
+
+        for _ in 0..10 {
+            now += time_until_next_tick(last_tick, interval, now);
+            last_tick = now;
+            // Simulate a slow player answering the refresh.
+            now += Duration::from_millis(20);
+            assert_eq!(
+                time_until_next_tick(last_tick, interval, now),
+                Duration::from_millis(80)
+            );
+        }
+
+        assert_eq!(last_tick - start, Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn it_ticks_on_time_while_flooded_with_unrelated_signals() {
+        let test_player = TestPlayer::new(TestPlayerState::default());
+        let player = test_player.player();
+        let mut tracker = ProgressTracker::new(&player, 100).unwrap();
+
+        test_player.flood_for(Duration::from_secs(1));
+        std::thread::sleep(Duration::from_millis(20));
+
+        for _ in 0..3 {
+            let start = Instant::now();
+            tracker.tick();
+            assert!(start.elapsed() < Duration::from_millis(150));
+        }
+    }
+
+    #[test]
+    fn it_clears_player_caches_on_force_refresh() {
+        let test_player = TestPlayer::new(TestPlayerState::default());
+        let player = test_player.player();
+        let mut tracker = ProgressTracker::new(&player, 100).unwrap();
+        assert_eq!(player.cached_supported_uri_schemes().unwrap(), vec!["file"]);
+
+        test_player.state().uri_schemes = vec!["file", "HTTPS"];
+        assert_eq!(player.cached_supported_uri_schemes().unwrap(), vec!["file"]);
+
+        tracker.force_refresh().unwrap();
+        assert_eq!(
+            player.cached_supported_uri_schemes().unwrap(),
+            vec!["file", "https"]
+        );
+    }
+
+    #[test]
+    fn it_records_removed_tracks() {
+        let test_player = TestPlayer::new(TestPlayerState {
+            tracks: Some(vec![String::from("/test/1"), String::from("/test/2")]),
+            ..TestPlayerState::default()
+        });
+        let player = test_player.player();
+        let mut tracker = ProgressTracker::new(&player, 100).unwrap();
+        tracker.track_list_mut().unwrap().set_operations_limit(10);
+
+        test_player.state().tracks = Some(vec![String::from("/test/2")]);
+        test_player.emit_track_removed("/test/1");
+        std::thread::sleep(Duration::from_millis(50));
+
+        let tick = tracker.tick();
+        assert!(tick.track_list_changed);
+        let track_list = tick.track_list.unwrap();
+        assert_eq!(track_list.ids(), &[TrackID::new("/test/2").unwrap()][..]);
+        assert!(track_list
+            .recent_operations()
+            .any(|operation| *operation
+                == TrackListOperation::Removed(TrackID::new("/test/1").unwrap())));
+    }
+
+    #[test]
+    fn it_refreshes_on_properties_changed_after_a_seek() {
+        let test_player = TestPlayer::new(TestPlayerState::default());
+        let player = test_player.player();
+        let mut tracker = ProgressTracker::new(&player, 100).unwrap();
+
+        test_player.state().title = String::from("Next track");
+        test_player.emit_seeked(10_000_000);
+        test_player.emit_player_properties_changed();
+        std::thread::sleep(Duration::from_millis(50));
+
+        // Both signals are read in the same tick.
+        let tick = tracker.tick();
+        assert!(tick.progress_changed);
+        assert_eq!(tick.progress.metadata().title(), Some("Next track"));
+    }
+
+    #[test]
+    fn it_does_not_wait_when_late() {
+        let now = Instant::now();
+        let last_tick = now - Duration::from_millis(500);
+
+        assert_eq!(
+            time_until_next_tick(last_tick, Duration::from_millis(100), now),
+            Duration::from_millis(0)
+        );
+        assert_eq!(
+            time_until_next_tick(now, Duration::from_millis(100), now),
+            Duration::from_millis(100)
+        );
+    }
 
     #[test]
     fn it_progresses_position_when_playing_at_microseconds() {
@@ -439,6 +1003,7 @@ mod test {
             loop_status: LoopStatus::None,
             rate: 1.0,
             position: Duration::from_micros_ext(1),
+            position_supported: true,
             current_volume: 0.0,
             instant: Instant::now(),
         };
@@ -456,10 +1021,93 @@ mod test {
             loop_status: LoopStatus::None,
             rate: 1.0,
             position: Duration::from_micros_ext(1336),
+            position_supported: true,
             current_volume: 0.0,
             instant: Instant::now() - Duration::from_millis(500),
         };
 
         assert_eq!(progress.position(), progress.initial_position());
     }
+
+    #[test]
+    fn it_moves_position_on_seek() {
+        let mut progress = Progress {
+            metadata: Metadata::new(String::from("id")),
+            playback_status: PlaybackStatus::Paused,
+            shuffle: false,
+            loop_status: LoopStatus::None,
+            rate: 1.0,
+            position: Duration::from_micros_ext(1336),
+            position_supported: true,
+            current_volume: 0.0,
+            instant: Instant::now() - Duration::from_millis(500),
+        };
+        let seeked_at = Instant::now();
+
+        progress.seeked_to(Duration::from_micros_ext(42_000_000));
+
+        assert_eq!(progress.initial_position(), Duration::from_secs(42));
+        assert_eq!(progress.position(), Duration::from_secs(42));
+        assert!(*progress.created_at() >= seeked_at);
+    }
+
+    #[test]
+    fn it_calculates_position_at_other_instants() {
+        let created_at = Instant::now();
+        let mut progress = Progress {
+            metadata: Metadata::new(String::from("id")),
+            playback_status: PlaybackStatus::Playing,
+            shuffle: false,
+            loop_status: LoopStatus::None,
+            rate: 2.0,
+            position: Duration::from_secs(10),
+            position_supported: true,
+            current_volume: 0.0,
+            instant: created_at,
+        };
+
+        assert_eq!(
+            progress.position_at(created_at + Duration::from_secs(3)),
+            Duration::from_secs(16)
+        );
+        assert_eq!(progress.position_at(created_at), Duration::from_secs(10));
+
+        progress.playback_status = PlaybackStatus::Paused;
+        assert_eq!(
+            progress.position_at(created_at + Duration::from_secs(3)),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn it_stops_progressing_when_paused_without_refresh() {
+        let mut progress = Progress {
+            metadata: Metadata::new(String::from("id")),
+            playback_status: PlaybackStatus::Playing,
+            shuffle: false,
+            loop_status: LoopStatus::None,
+            rate: 1.0,
+            position: Duration::from_secs(10),
+            position_supported: true,
+            current_volume: 0.0,
+            instant: Instant::now() - Duration::from_secs(2),
+        };
+
+        let failed = || DBusError::Miscellaneous(String::from("Not supported"));
+        let refresh_failed = || Err(failed());
+        assert!(progress.refresh_from(refresh_failed, || Ok(PlaybackStatus::Paused)));
+        assert_eq!(progress.playback_status(), PlaybackStatus::Paused);
+
+        // Nothing changes if the status could not be read, or is the same as before.
+        assert!(!progress.refresh_from(refresh_failed, || Err(failed())));
+        assert!(!progress.refresh_from(refresh_failed, || Ok(PlaybackStatus::Paused)));
+
+        let paused_position = progress.position();
+        assert!(paused_position >= Duration::from_secs(12));
+        assert_eq!(progress.initial_position(), paused_position);
+        assert_eq!(
+            progress.position_at(Instant::now() + Duration::from_secs(5)),
+            paused_position
+        );
+    }
 }
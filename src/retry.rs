@@ -0,0 +1,87 @@
+use std::thread;
+use std::time::Duration;
+
+use super::DBusError;
+
+/// Calls `f` until it succeeds, for at most `attempts` attempts, as long as it fails with a
+/// [transient](DBusError::is_transient) error.
+///
+/// Between attempts the current thread sleeps for `backoff`, which is doubled after every failed
+/// attempt. Errors that are not transient are returned immediately, as is the last error if all
+/// attempts fail. `f` is always called at least once.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use mpris::PlayerFinder;
+/// use std::time::Duration;
+///
+/// let player = PlayerFinder::new().unwrap().find_active().unwrap();
+/// let status = mpris::retry(3, Duration::from_millis(100), || player.get_playback_status());
+/// ```
+pub fn retry<T, F>(attempts: u32, backoff: Duration, mut f: F) -> Result<T, DBusError>
+where
+    F: FnMut() -> Result<T, DBusError>,
+{
+    let mut backoff = backoff;
+    let mut attempt = 1;
+
+    loop {
+        match f() {
+            Err(error) if attempt < attempts && error.is_transient() => {
+                thread::sleep(backoff);
+                backoff = backoff.checked_mul(2).unwrap_or(backoff);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transport_error(name: &str) -> DBusError {
+        DBusError::TransportError(dbus::Error::new_custom(name, "Test error"))
+    }
+
+    #[test]
+    fn it_retries_transient_errors() {
+        let mut calls = 0;
+        let result = retry(3, Duration::from_millis(0), || {
+            calls += 1;
+            if calls < 3 {
+                Err(transport_error("org.freedesktop.DBus.Error.NoReply"))
+            } else {
+                Ok(calls)
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn it_gives_up_after_all_attempts() {
+        let mut calls = 0;
+        let result: Result<(), DBusError> = retry(2, Duration::from_millis(0), || {
+            calls += 1;
+            Err(transport_error("org.freedesktop.DBus.Error.Timeout"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn it_does_not_retry_permanent_errors() {
+        let mut calls = 0;
+        let result: Result<(), DBusError> = retry(5, Duration::from_millis(0), || {
+            calls += 1;
+            Err(transport_error("org.freedesktop.DBus.Error.ServiceUnknown"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
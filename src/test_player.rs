@@ -0,0 +1,331 @@
+//! A fake MPRIS player on the session bus, for tests that need to talk to a real [`Player`].
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use dbus::arg::{RefArg, Variant};
+use dbus::blocking::Connection as BlockingConnection;
+use dbus::channel::Sender as _;
+use dbus::ffidisp::Connection;
+use dbus::strings::{ErrorName, Path};
+use dbus::Message;
+
+use crate::player::{Player, MPRIS2_PATH, MPRIS2_PREFIX};
+
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const TRACK_LIST_INTERFACE: &str = "org.mpris.MediaPlayer2.TrackList";
+const NOT_SUPPORTED: &str = "org.freedesktop.DBus.Error.NotSupported";
+
+type Properties = HashMap<String, Variant<Box<dyn RefArg>>>;
+
+/// State of a [`TestPlayer`]. Changes are seen by the next call to the player, but no signals
+/// are sent for them.
+#[derive(Debug)]
+pub(crate) struct TestPlayerState {
+    pub(crate) playback_status: &'static str,
+    pub(crate) title: String,
+    pub(crate) position_in_us: i64,
+    pub(crate) uri_schemes: Vec<&'static str>,
+    /// Fail `GetAll` on the `Player` interface.
+    pub(crate) fail_get_all: bool,
+    /// Fail reading the `Position` property.
+    pub(crate) fail_position: bool,
+    /// Track IDs on the track list. The `TrackList` interface is only exported if this is set.
+    pub(crate) tracks: Option<Vec<String>>,
+}
+
+impl Default for TestPlayerState {
+    fn default() -> Self {
+        TestPlayerState {
+            playback_status: "Playing",
+            title: String::from("Test track"),
+            position_in_us: 0,
+            uri_schemes: vec!["file"],
+            fail_get_all: false,
+            fail_position: false,
+            tracks: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    Seeked(u64),
+    PlayerPropertiesChanged,
+    TrackRemoved(String),
+    Flood(Duration),
+    Stop,
+}
+
+/// A fake MPRIS player, running on its own thread and connection until dropped.
+#[derive(Debug)]
+pub(crate) struct TestPlayer {
+    bus_name: String,
+    state: Arc<Mutex<TestPlayerState>>,
+    commands: Sender<Command>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TestPlayer {
+    pub(crate) fn new(state: TestPlayerState) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let bus_name = format!(
+            "{}test{}x{}",
+            MPRIS2_PREFIX,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        );
+
+        let state = Arc::new(Mutex::new(state));
+        let (commands, receiver) = channel();
+        let (ready_sender, ready) = channel();
+
+        let thread = {
+            let bus_name = bus_name.clone();
+            let state = Arc::clone(&state);
+            thread::spawn(move || {
+                let connection =
+                    BlockingConnection::new_session().expect("Tests need a D-Bus session bus");
+                connection
+                    .request_name(bus_name.as_str(), false, true, true)
+                    .expect("Could not register test player name");
+                ready_sender.send(()).unwrap();
+                run(&connection, &state, &receiver);
+            })
+        };
+        ready.recv().expect("Test player failed to start");
+
+        TestPlayer {
+            bus_name,
+            state,
+            commands,
+            thread: Some(thread),
+        }
+    }
+
+    /// A [`Player`] for this test player, on a new connection.
+    pub(crate) fn player(&self) -> Player {
+        let connection = Connection::new_session().expect("Tests need a D-Bus session bus");
+        Player::new(connection, self.bus_name.clone(), 500).expect("Could not create Player")
+    }
+
+    pub(crate) fn state(&self) -> MutexGuard<'_, TestPlayerState> {
+        self.state.lock().unwrap()
+    }
+
+    pub(crate) fn emit_seeked(&self, position_in_us: u64) {
+        self.commands.send(Command::Seeked(position_in_us)).unwrap();
+    }
+
+    pub(crate) fn emit_player_properties_changed(&self) {
+        self.commands
+            .send(Command::PlayerPropertiesChanged)
+            .unwrap();
+    }
+
+    pub(crate) fn emit_track_removed(&self, id: &str) {
+        self.commands
+            .send(Command::TrackRemoved(String::from(id)))
+            .unwrap();
+    }
+
+    /// Keep sending signals that no [`Player`] cares about for the given duration.
+    pub(crate) fn flood_for(&self, duration: Duration) {
+        self.commands.send(Command::Flood(duration)).unwrap();
+    }
+}
+
+impl Drop for TestPlayer {
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(
+    connection: &BlockingConnection,
+    state: &Mutex<TestPlayerState>,
+    commands: &Receiver<Command>,
+) {
+    let mut flood_until = None;
+
+    loop {
+        while let Ok(Some(message)) = connection
+            .channel()
+            .blocking_pop_message(Duration::from_millis(1))
+        {
+            if let Some(reply) = reply_to(&message, &state.lock().unwrap()) {
+                let _ = connection.send(reply);
+            }
+        }
+
+        while let Ok(command) = commands.try_recv() {
+            let signal = match command {
+                Command::Seeked(position_in_us) => {
+                    signal(PLAYER_INTERFACE, "Seeked").append1(position_in_us)
+                }
+                Command::PlayerPropertiesChanged => properties_changed(PLAYER_INTERFACE),
+                Command::TrackRemoved(id) => {
+                    signal(TRACK_LIST_INTERFACE, "TrackRemoved").append1(Path::from(id))
+                }
+                Command::Flood(duration) => {
+                    flood_until = Some(Instant::now() + duration);
+                    continue;
+                }
+                Command::Stop => return,
+            };
+            let _ = connection.send(signal);
+        }
+
+        if flood_until.map_or(false, |until| Instant::now() < until) {
+            for _ in 0..50 {
+                let _ = connection.send(properties_changed("org.example.Unrelated"));
+            }
+        }
+    }
+}
+
+fn signal(interface: &str, member: &str) -> Message {
+    Message::signal(&MPRIS2_PATH.into(), &interface.into(), &member.into())
+}
+
+fn properties_changed(interface: &str) -> Message {
+    signal("org.freedesktop.DBus.Properties", "PropertiesChanged").append3(
+        interface,
+        Properties::new(),
+        Vec::<String>::new(),
+    )
+}
+
+fn reply_to(message: &Message, state: &TestPlayerState) -> Option<Message> {
+    if message.msg_type() != dbus::MessageType::MethodCall {
+        return None;
+    }
+
+    let member = message.member()?;
+    let reply = match &*member {
+        "Introspect" => Ok(message.method_return().append1(introspection(state))),
+        "Get" => {
+            let (interface, name): (String, String) = message.read2().ok()?;
+            get_property(state, &interface, &name)
+                .map(|value| message.method_return().append1(Variant(value)))
+        }
+        "GetAll" => {
+            let interface: String = message.read1().ok()?;
+            get_all_properties(state, &interface)
+                .map(|properties| message.method_return().append1(properties))
+        }
+        // Accepted, but never announced with a TrackAdded signal.
+        "AddTrack" => Ok(message.method_return()),
+        "GetTracksMetadata" => {
+            let ids: Vec<Path<'_>> = message.read1().ok()?;
+            let metadata: Vec<Properties> = ids
+                .into_iter()
+                .map(|id| track_metadata(&id, "Track"))
+                .collect();
+            Ok(message.method_return().append1(metadata))
+        }
+        _ => Err(NOT_SUPPORTED),
+    };
+
+    Some(reply.unwrap_or_else(|error_name| {
+        message.error(
+            &ErrorName::from(error_name),
+            &std::ffi::CString::new("Not supported by the test player").unwrap(),
+        )
+    }))
+}
+
+fn introspection(state: &TestPlayerState) -> String {
+    let mut xml = format!(
+        r#"<node><interface name="org.mpris.MediaPlayer2"/><interface name="{}"/>"#,
+        PLAYER_INTERFACE
+    );
+    if state.tracks.is_some() {
+        xml.push_str(&format!(r#"<interface name="{}"/>"#, TRACK_LIST_INTERFACE));
+    }
+    xml.push_str("</node>");
+    xml
+}
+
+fn track_metadata(id: &str, title: &str) -> Properties {
+    let mut metadata = Properties::new();
+    metadata.insert(
+        String::from("mpris:trackid"),
+        Variant(Box::new(Path::from(id.to_owned()))),
+    );
+    metadata.insert(
+        String::from("xesam:title"),
+        Variant(Box::new(String::from(title))),
+    );
+    metadata
+}
+
+fn player_properties(state: &TestPlayerState) -> Properties {
+    let mut properties = Properties::new();
+    properties.insert(
+        String::from("PlaybackStatus"),
+        Variant(Box::new(String::from(state.playback_status))),
+    );
+    properties.insert(
+        String::from("Metadata"),
+        Variant(Box::new(track_metadata("/test/current", &state.title))),
+    );
+    properties.insert(String::from("Rate"), Variant(Box::new(1.0f64)));
+    properties.insert(
+        String::from("Position"),
+        Variant(Box::new(state.position_in_us)),
+    );
+    properties
+}
+
+fn get_property(
+    state: &TestPlayerState,
+    interface: &str,
+    name: &str,
+) -> Result<Box<dyn RefArg>, &'static str> {
+    match (interface, name) {
+        ("org.mpris.MediaPlayer2", "Identity") => Ok(Box::new(String::from("Test player"))),
+        ("org.mpris.MediaPlayer2", "HasTrackList") => Ok(Box::new(state.tracks.is_some())),
+        ("org.mpris.MediaPlayer2", "SupportedUriSchemes") => Ok(Box::new(
+            state
+                .uri_schemes
+                .iter()
+                .map(|scheme| String::from(*scheme))
+                .collect::<Vec<_>>(),
+        )),
+        (PLAYER_INTERFACE, "Position") if state.fail_position => Err(NOT_SUPPORTED),
+        (PLAYER_INTERFACE, _) => player_properties(state)
+            .remove(name)
+            .map(|value| value.0)
+            .ok_or("org.freedesktop.DBus.Error.UnknownProperty"),
+        (TRACK_LIST_INTERFACE, "Tracks") => match state.tracks {
+            Some(ref tracks) => Ok(Box::new(
+                tracks
+                    .iter()
+                    .map(|id| Path::from(id.clone()))
+                    .collect::<Vec<_>>(),
+            )),
+            None => Err("org.freedesktop.DBus.Error.UnknownInterface"),
+        },
+        (TRACK_LIST_INTERFACE, "CanEditTracks") => Ok(Box::new(false)),
+        _ => Err("org.freedesktop.DBus.Error.UnknownProperty"),
+    }
+}
+
+fn get_all_properties(
+    state: &TestPlayerState,
+    interface: &str,
+) -> Result<Properties, &'static str> {
+    match interface {
+        PLAYER_INTERFACE if state.fail_get_all => Err(NOT_SUPPORTED),
+        PLAYER_INTERFACE => Ok(player_properties(state)),
+        _ => Err("org.freedesktop.DBus.Error.UnknownInterface"),
+    }
+}
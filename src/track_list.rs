@@ -1,6 +1,6 @@
 use super::{DBusError, Metadata, Player};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::iter::{FromIterator, IntoIterator};
 use thiserror::Error;
@@ -43,6 +43,32 @@ pub struct TrackID(pub(crate) String);
 pub struct TrackList {
     ids: Vec<TrackID>,
     metadata_cache: RefCell<HashMap<TrackID, Metadata>>,
+    operations: VecDeque<TrackListOperation>,
+    operations_limit: usize,
+}
+
+/// A change that was applied to a [`TrackList`].
+///
+/// See [`TrackList::recent_operations`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackListOperation {
+    /// A track was added to the list.
+    Added(TrackID),
+
+    /// A track was removed from the list.
+    Removed(TrackID),
+
+    /// A track on the list had its metadata replaced, possibly changing its ID.
+    MetadataChanged {
+        /// The id of the track *before* the change.
+        old_id: TrackID,
+
+        /// The id of the track *after* the change.
+        new_id: TrackID,
+    },
+
+    /// The entire list was replaced, reloaded or cleared.
+    Replaced,
 }
 
 /// TrackList-related errors.
@@ -180,14 +206,46 @@ impl TrackList {
         TrackList {
             metadata_cache: RefCell::new(HashMap::with_capacity(ids.len())),
             ids,
+            operations: VecDeque::new(),
+            operations_limit: 0,
+        }
+    }
+
+    /// Start recording the operations that are applied to this list, keeping at most `limit` of
+    /// the most recent ones. Setting the limit to `0` stops recording and forgets all recorded
+    /// operations.
+    ///
+    /// Recording is disabled by default. When enabled, it can help with debugging issues where the
+    /// list gets out of sync with the player's actual tracklist.
+    ///
+    /// See [`recent_operations`](Self::recent_operations).
+    pub fn set_operations_limit(&mut self, limit: usize) {
+        self.operations_limit = limit;
+        while self.operations.len() > limit {
+            self.operations.pop_front();
         }
     }
 
+    /// Iterates the most recently applied operations on this list, oldest first.
+    ///
+    /// Nothing is recorded unless enabled with [`set_operations_limit`](Self::set_operations_limit).
+    pub fn recent_operations(&self) -> impl Iterator<Item = &TrackListOperation> {
+        self.operations.iter()
+    }
+
     /// Get a list of [`TrackID`]s that are part of this [`TrackList`]. The order matters.
     pub fn ids(&self) -> &[TrackID] {
         self.ids.as_ref()
     }
 
+    /// Get an owned copy of the [`TrackID`]s that are part of this [`TrackList`], in order.
+    ///
+    /// This is useful when you want to iterate over a snapshot of the list while modifying the
+    /// list itself.
+    pub fn ids_vec(&self) -> Vec<TrackID> {
+        self.ids.clone()
+    }
+
     /// Returns the number of tracks on the list.
     pub fn len(&self) -> usize {
         self.ids.len()
@@ -203,6 +261,71 @@ impl TrackList {
         self.ids.get(index)
     }
 
+    /// Returns the index of the given [`TrackID`] on the list, or [`None`] if it is not on the
+    /// list.
+    pub fn position_of(&self, id: &TrackID) -> Option<usize> {
+        self.ids.iter().position(|item_id| item_id == id)
+    }
+
+    /// Checks if the given [`TrackID`] is on the list.
+    pub fn contains(&self, id: &TrackID) -> bool {
+        self.ids.contains(id)
+    }
+
+    /// Returns the index of the first track on the list with the given `xesam:url`, or [`None`]
+    /// if no track has it.
+    ///
+    /// Use this to find a track when its [`TrackID`] cannot be relied on, for example because
+    /// the player changes IDs when the list is modified.
+    ///
+    /// This needs the [`Metadata`] of every track, so any tracks missing from the cache will be
+    /// loaded from the player first, like [`complete_cache`](Self::complete_cache) does.
+    pub fn index_by_url(
+        &self,
+        player: &Player,
+        url: &str,
+    ) -> Result<Option<usize>, TrackListError> {
+        self.complete_cache(player)?;
+        Ok(self.cached_index_by_url(url))
+    }
+
+    fn cached_index_by_url(&self, url: &str) -> Option<usize> {
+        let cache = self.metadata_cache.borrow();
+        self.ids
+            .iter()
+            .position(|id| cache.get(id).and_then(Metadata::url) == Some(url))
+    }
+
+    /// Returns the [`TrackID`] that follows the given one on the list.
+    ///
+    /// Returns [`None`] if `id` is the last track, or if it is not on the list.
+    pub fn next_id(&self, id: &TrackID) -> Option<&TrackID> {
+        self.id_after(id, false)
+    }
+
+    /// Returns the [`TrackID`] that comes before the given one on the list.
+    ///
+    /// Returns [`None`] if `id` is the first track, or if it is not on the list.
+    pub fn previous_id(&self, id: &TrackID) -> Option<&TrackID> {
+        self.position_of(id)?
+            .checked_sub(1)
+            .and_then(|index| self.get(index))
+    }
+
+    /// Returns the index of the currently playing track on the list, given its [`TrackID`]. This
+    /// is usually the [`Metadata::track_id`] of the player's current [`Metadata`].
+    ///
+    /// Returns [`None`] if the track is not on the list.
+    pub fn current_index(&self, current: &TrackID) -> Option<usize> {
+        self.position_of(current)
+    }
+
+    /// Checks if `id` is the currently playing track, given the [`TrackID`] of the current track.
+    /// Tracks that are not on the list are never current.
+    pub fn is_current(&self, id: &TrackID, current: &TrackID) -> bool {
+        id == current && self.current_index(current).is_some()
+    }
+
     /// Insert a new track (via its metadata) after another one. If the provided ID cannot be found
     /// on the list, it will be inserted at the end.
     ///
@@ -216,7 +339,7 @@ impl TrackList {
             None => return,
         };
 
-        let index = self.index_of_id(after).unwrap_or_else(|| self.ids.len());
+        let index = self.position_of(after).unwrap_or_else(|| self.ids.len());
 
         // Vec::insert inserts BEFORE the given index, but we need to insert *after* the index.
         if index >= self.ids.len() {
@@ -225,6 +348,7 @@ impl TrackList {
             self.ids.insert(index + 1, new_id.clone());
         }
 
+        self.record_operation(TrackListOperation::Added(new_id.clone()));
         self.change_metadata(|cache| cache.insert(new_id, metadata));
     }
 
@@ -232,14 +356,46 @@ impl TrackList {
     ///
     /// **Note:** If the same id is present multiple times, all of them will be removed.
     pub fn remove(&mut self, id: &TrackID) {
+        let len_before = self.ids.len();
         self.ids.retain(|existing_id| existing_id != id);
 
+        if self.ids.len() != len_before {
+            self.record_operation(TrackListOperation::Removed(id.clone()));
+        }
         self.change_metadata(|cache| cache.remove(id));
     }
 
+    /// Keeps only the tracks that the predicate returns [`true`] for. The predicate is given the
+    /// [`TrackID`] of each track along with its cached [`Metadata`], if there is any.
+    ///
+    /// Metadata of removed tracks will be removed from the cache.
+    ///
+    /// **NOTE:** Like [`remove`](Self::remove), this is *not* something that will affect a
+    /// player's actual tracklist; this is strictly for client-side representation.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&TrackID, Option<&Metadata>) -> bool,
+    {
+        let cache = self.metadata_cache.get_mut();
+        let mut removed = Vec::new();
+        self.ids.retain(|id| {
+            let keep = f(id, cache.get(id));
+            if !keep {
+                removed.push(id.clone());
+            }
+            keep
+        });
+
+        for id in removed {
+            self.record_operation(TrackListOperation::Removed(id));
+        }
+        self.clear_extra_cache();
+    }
+
     /// Clears the entire list and cache.
     pub fn clear(&mut self) {
         self.ids.clear();
+        self.record_operation(TrackListOperation::Replaced);
         self.change_metadata(|cache| cache.clear());
     }
 
@@ -247,6 +403,7 @@ impl TrackList {
     /// possible.
     pub fn replace(&mut self, other: TrackList) {
         self.ids = other.ids;
+        self.record_operation(TrackListOperation::Replaced);
         let other_cache = other.metadata_cache.into_inner();
 
         self.change_metadata(|self_cache| {
@@ -283,8 +440,12 @@ impl TrackList {
         new_metadata: Metadata,
     ) -> Option<TrackID> {
         if let Some(new_id) = new_metadata.track_id() {
-            if let Some(index) = self.index_of_id(old_id) {
+            if let Some(index) = self.position_of(old_id) {
                 self.ids[index] = new_id.to_owned();
+                self.record_operation(TrackListOperation::MetadataChanged {
+                    old_id: old_id.clone(),
+                    new_id: new_id.clone(),
+                });
                 self.change_metadata(|cache| cache.insert(new_id.to_owned(), new_metadata));
 
                 return Some(new_id);
@@ -299,16 +460,51 @@ impl TrackList {
     ///
     /// [`Metadata`] will be loaded from the provided player when not present in the metadata cache.
     /// If metadata loading fails, then a [`DBusError`] will be returned instead of the iterator.
+    ///
+    /// The iterator owns copies of the [`Metadata`], so it does not borrow the cache while
+    /// iterating. Several iterators can be alive at once, and the cache can be updated while they
+    /// are.
     pub fn metadata_iter(&self, player: &Player) -> Result<MetadataIter, TrackListError> {
         self.complete_cache(player)?;
-        let metadata: HashMap<_, _> = self.metadata_cache.clone().into_inner();
-        let ids = self.ids.clone();
+        Ok(self.cached_metadata_iter())
+    }
+
+    /// Iterates the list using only what is already in the cache. The cache is only borrowed
+    /// while copying the metadata of the tracks on the list.
+    fn cached_metadata_iter(&self) -> MetadataIter {
+        let cache = self.metadata_cache.borrow();
+        let metadata: HashMap<_, _> = self
+            .ids
+            .iter()
+            .filter_map(|id| cache.get(id).map(|metadata| (id.clone(), metadata.clone())))
+            .collect();
 
-        Ok(MetadataIter {
+        MetadataIter {
             current: 0,
-            order: ids,
+            order: self.ids.clone(),
             metadata,
-        })
+        }
+    }
+
+    /// Iterates the tracks in the tracklist, returning each [`TrackID`] together with the
+    /// [`Metadata`] of that track.
+    ///
+    /// This works like [`metadata_iter`](Self::metadata_iter), but keeps the [`TrackID`] from the
+    /// list even for players that leave `mpris:trackid` out of the track's metadata.
+    pub fn iter_with_ids(
+        &self,
+        player: &Player,
+    ) -> Result<impl Iterator<Item = (TrackID, Metadata)>, TrackListError> {
+        self.complete_cache(player)?;
+        Ok(self.cached_iter_with_ids())
+    }
+
+    /// Iterates the list together with its [`TrackID`]s using only what is already in the cache.
+    fn cached_iter_with_ids(&self) -> impl Iterator<Item = (TrackID, Metadata)> {
+        self.ids
+            .clone()
+            .into_iter()
+            .zip(self.cached_metadata_iter())
     }
 
     /// Reloads the tracklist from the given player. This can be compared with loading a new track
@@ -318,6 +514,7 @@ impl TrackList {
     /// Cache for tracks that are no longer part of the player's tracklist will be removed.
     pub fn reload(&mut self, player: &Player) -> Result<(), TrackListError> {
         self.ids = player.get_track_list()?.ids;
+        self.record_operation(TrackListOperation::Replaced);
         self.clear_extra_cache();
         Ok(())
     }
@@ -351,19 +548,46 @@ impl TrackList {
             .collect();
         if !ids.is_empty() {
             let metadata = player.get_tracks_metadata(&ids)?;
+            self.cache_metadata(ids, metadata)?;
+        }
+        Ok(())
+    }
 
-            // We only have a &self reference, so fail if we cannot borrow.
-            let mut cache = self.metadata_cache.try_borrow_mut()?;
-
-            for info in metadata.into_iter() {
-                if let Some(id) = info.track_id() {
-                    cache.insert(id, info);
-                }
-            }
+    /// Adds loaded metadata to the cache, keyed by its own `mpris:trackid`.
+    ///
+    /// Metadata without a [`TrackID`] is paired with the ID requested at the same position, but
+    /// only if the player returned one entry per requested ID. Otherwise there is no telling which
+    /// track it belongs to, and it is discarded.
+    fn cache_metadata(
+        &self,
+        ids: Vec<TrackID>,
+        metadata: Vec<Metadata>,
+    ) -> Result<(), TrackListError> {
+        // We only have a &self reference, so fail if we cannot borrow.
+        let mut cache = self.metadata_cache.try_borrow_mut()?;
+        let pair_by_position = ids.len() == metadata.len();
+        for (index, metadata) in metadata.into_iter().enumerate() {
+            let id = match metadata.track_id() {
+                Some(id) => id,
+                None if pair_by_position => ids[index].clone(),
+                None => continue,
+            };
+            cache.insert(id, metadata);
         }
         Ok(())
     }
 
+    fn record_operation(&mut self, operation: TrackListOperation) {
+        if self.operations_limit == 0 {
+            return;
+        }
+
+        if self.operations.len() >= self.operations_limit {
+            self.operations.pop_front();
+        }
+        self.operations.push_back(operation);
+    }
+
     /// Change metadata cache. As this requires a `&mut self`, the borrow is guaranteed to work.
     fn change_metadata<T, F>(&mut self, f: F) -> T
     where
@@ -396,12 +620,15 @@ impl TrackList {
         });
     }
 
-    fn index_of_id(&self, id: &TrackID) -> Option<usize> {
-        self.ids
-            .iter()
-            .enumerate()
-            .find(|(_, item_id)| *item_id == id)
-            .map(|(index, _)| index)
+    /// Returns the [`TrackID`] that follows the given one on the list. If `wrap` is [`true`], the
+    /// first track follows the last one.
+    pub(crate) fn id_after(&self, id: &TrackID, wrap: bool) -> Option<&TrackID> {
+        let index = self.position_of(id)?;
+        match self.ids.get(index + 1) {
+            Some(next) => Some(next),
+            None if wrap => self.ids.first(),
+            None => None,
+        }
     }
 }
 
@@ -447,6 +674,7 @@ mod tests {
 
     mod track_list {
         use super::*;
+        use crate::MetadataValue;
 
         #[test]
         fn it_inserts_after_given_id() {
@@ -456,6 +684,7 @@ mod tests {
             let mut list = TrackList {
                 ids: vec![first, third],
                 metadata_cache: RefCell::new(HashMap::new()),
+                ..Default::default()
             };
 
             let metadata = Metadata::new("/path/new");
@@ -484,6 +713,7 @@ mod tests {
             let mut list = TrackList {
                 ids: vec![first, third],
                 metadata_cache: RefCell::new(HashMap::new()),
+                ..Default::default()
             };
 
             let metadata = Metadata::new("/path/new");
@@ -515,5 +745,204 @@ mod tests {
             assert_eq!(&list.ids, &[track_id("/path/new")]);
             assert!(list.ids_without_cache().is_empty());
         }
+
+        #[test]
+        fn it_retains_tracks_matching_predicate() {
+            let mut list = TrackList::new(vec![track_id("/path/1"), track_id("/path/2")]);
+            list.add_metadata(Metadata::new("/path/1"));
+            list.add_metadata(Metadata::new("/path/2"));
+            list.insert(&track_id("/path/2"), Metadata::new("/path/3"));
+
+            list.retain(|id, metadata| {
+                assert!(metadata.is_some());
+                id != &track_id("/path/2")
+            });
+
+            assert_eq!(&list.ids, &[track_id("/path/1"), track_id("/path/3")]);
+            assert!(!list
+                .metadata_cache
+                .borrow()
+                .contains_key(&track_id("/path/2")));
+        }
+
+        #[test]
+        fn it_finds_the_id_after_another() {
+            let list = TrackList::new(vec![track_id("/path/1"), track_id("/path/2")]);
+
+            assert_eq!(
+                list.id_after(&track_id("/path/1"), false),
+                Some(&track_id("/path/2"))
+            );
+            assert_eq!(list.id_after(&track_id("/path/2"), false), None);
+            assert_eq!(
+                list.id_after(&track_id("/path/2"), true),
+                Some(&track_id("/path/1"))
+            );
+            assert_eq!(list.id_after(&track_id("/path/missing"), true), None);
+        }
+
+        #[test]
+        fn it_iterates_several_times_at_once() {
+            let mut list = TrackList::new(vec![track_id("/path/1")]);
+            list.add_metadata(Metadata::new("/path/1"));
+
+            let mut first = list.cached_metadata_iter();
+            let second = list.cached_metadata_iter();
+            assert!(first.next().is_some());
+
+            // The cache must not be borrowed by the live iterators.
+            assert!(list.metadata_cache.try_borrow_mut().is_ok());
+
+            assert_eq!(
+                second
+                    .map(|metadata| metadata.track_id())
+                    .collect::<Vec<_>>(),
+                vec![Some(track_id("/path/1"))]
+            );
+            assert_eq!(first.next(), None);
+        }
+
+        #[test]
+        fn it_keeps_metadata_of_tracks_without_a_track_id() {
+            let list = TrackList::new(vec![track_id("/path/1"), track_id("/path/2")]);
+            let mut values = HashMap::new();
+            values.insert(String::from("xesam:title"), MetadataValue::from("Second"));
+
+            list.cache_metadata(
+                vec![track_id("/path/1"), track_id("/path/2")],
+                vec![Metadata::from(HashMap::new()), Metadata::from(values)],
+            )
+            .unwrap();
+
+            let tracks: Vec<_> = list.cached_iter_with_ids().collect();
+            assert_eq!(tracks[0].0, track_id("/path/1"));
+            assert_eq!(tracks[1].0, track_id("/path/2"));
+            assert_eq!(tracks[1].1.title(), Some("Second"));
+        }
+
+        #[test]
+        fn it_caches_metadata_by_its_own_track_id() {
+            let list = TrackList::new(vec![
+                track_id("/path/1"),
+                track_id("/path/2"),
+                track_id("/path/3"),
+            ]);
+            let metadata = |id: &str, title: &str| {
+                let mut values = HashMap::new();
+                values.insert(String::from("mpris:trackid"), MetadataValue::from(id));
+                values.insert(String::from("xesam:title"), MetadataValue::from(title));
+                Metadata::from(values)
+            };
+            let mut untagged = HashMap::new();
+            untagged.insert(String::from("xesam:title"), MetadataValue::from("Unknown"));
+
+            // Reordered, with one track missing and one entry without an ID.
+            list.cache_metadata(
+                vec![
+                    track_id("/path/1"),
+                    track_id("/path/2"),
+                    track_id("/path/3"),
+                ],
+                vec![metadata("/path/3", "Third"), metadata("/path/1", "First")],
+            )
+            .unwrap();
+            list.cache_metadata(
+                vec![track_id("/path/2"), track_id("/path/3")],
+                vec![Metadata::from(untagged)],
+            )
+            .unwrap();
+
+            let cache = list.metadata_cache.borrow();
+            assert_eq!(cache[&track_id("/path/1")].title(), Some("First"));
+            assert_eq!(cache[&track_id("/path/3")].title(), Some("Third"));
+            assert!(!cache.contains_key(&track_id("/path/2")));
+        }
+
+        #[test]
+        fn it_finds_tracks_by_url() {
+            let mut list = TrackList::new(vec![track_id("/path/1"), track_id("/path/2")]);
+            let mut values = HashMap::new();
+            values.insert(
+                String::from("mpris:trackid"),
+                MetadataValue::from("/path/2"),
+            );
+            values.insert(
+                String::from("xesam:url"),
+                MetadataValue::from("file:///song.ogg"),
+            );
+            list.add_metadata(Metadata::from(values));
+
+            assert!(list.contains(&track_id("/path/1")));
+            assert!(!list.contains(&track_id("/path/3")));
+
+            assert_eq!(list.cached_index_by_url("file:///song.ogg"), Some(1));
+            assert_eq!(list.cached_index_by_url("file:///other.ogg"), None);
+        }
+
+        #[test]
+        fn it_finds_neighboring_ids() {
+            let list = TrackList::new(vec![
+                track_id("/path/1"),
+                track_id("/path/2"),
+                track_id("/path/3"),
+            ]);
+
+            assert_eq!(list.position_of(&track_id("/path/3")), Some(2));
+            assert_eq!(list.position_of(&track_id("/path/missing")), None);
+
+            assert_eq!(
+                list.next_id(&track_id("/path/2")),
+                Some(&track_id("/path/3"))
+            );
+            assert_eq!(list.next_id(&track_id("/path/3")), None);
+            assert_eq!(
+                list.previous_id(&track_id("/path/2")),
+                Some(&track_id("/path/1"))
+            );
+            assert_eq!(list.previous_id(&track_id("/path/1")), None);
+            assert_eq!(list.previous_id(&track_id("/path/missing")), None);
+        }
+
+        #[test]
+        fn it_records_recent_operations_when_enabled() {
+            let mut list = TrackList::new(vec![track_id("/path/1")]);
+            list.remove(&track_id("/path/1"));
+            assert_eq!(list.recent_operations().count(), 0);
+
+            list.set_operations_limit(2);
+            list.insert(&track_id("/path/1"), Metadata::new("/path/2"));
+            list.insert(&track_id("/path/2"), Metadata::new("/path/3"));
+            list.remove(&track_id("/path/2"));
+            list.remove(&track_id("/path/missing"));
+
+            assert_eq!(
+                list.recent_operations().collect::<Vec<_>>(),
+                vec![
+                    &TrackListOperation::Added(track_id("/path/3")),
+                    &TrackListOperation::Removed(track_id("/path/2")),
+                ]
+            );
+
+            list.retain(|id, _| id != &track_id("/path/3"));
+            assert_eq!(
+                list.recent_operations().last(),
+                Some(&TrackListOperation::Removed(track_id("/path/3")))
+            );
+
+            list.set_operations_limit(0);
+            assert_eq!(list.recent_operations().count(), 0);
+        }
+
+        #[test]
+        fn it_finds_the_current_track() {
+            let list = TrackList::new(vec![track_id("/path/1"), track_id("/path/2")]);
+
+            assert_eq!(list.current_index(&track_id("/path/2")), Some(1));
+            assert_eq!(list.current_index(&track_id("/path/3")), None);
+
+            assert!(list.is_current(&track_id("/path/2"), &track_id("/path/2")));
+            assert!(!list.is_current(&track_id("/path/1"), &track_id("/path/2")));
+            assert!(!list.is_current(&track_id("/path/3"), &track_id("/path/3")));
+        }
     }
 }